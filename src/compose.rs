@@ -0,0 +1,1154 @@
+// src/compose.rs
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::compose_file::{self, ComposeProject};
+use crate::logs::LogOptions;
+use crate::output::{self, Output};
+use crate::process::{self, ExecOptions};
+use crate::target::Target;
+use crate::{RestartStrategy, ServiceState};
+
+/// Compose file names, checked in a sensible order within each directory
+const COMPOSE_FILE_NAMES: [&str; 6] = [
+    "docker-compose.yml",
+    "docker-compose.yaml",
+    "compose.yml",
+    "compose.yaml",
+    "container-compose.yml",
+    "container-compose.yaml",
+];
+
+/// How long to wait for containers to become healthy unless told otherwise
+pub const DEFAULT_WAIT: Duration = Duration::from_secs(60);
+
+/// Pause between container status checks while waiting
+const WAIT_POLL: Duration = Duration::from_secs(1);
+
+/// A compose implementation tickle can drive
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComposeCli {
+    /// `docker compose` (the v2 plugin)
+    DockerPlugin,
+    /// The legacy standalone `docker-compose`
+    DockerCompose,
+    /// `podman compose`
+    Podman,
+    /// The standalone `podman-compose`
+    PodmanCompose,
+    /// `nerdctl compose` (containerd)
+    Nerdctl,
+}
+
+impl ComposeCli {
+    /// In order of preference
+    const ALL: [ComposeCli; 5] = [
+        ComposeCli::DockerPlugin,
+        ComposeCli::DockerCompose,
+        ComposeCli::Podman,
+        ComposeCli::PodmanCompose,
+        ComposeCli::Nerdctl,
+    ];
+
+    /// Program and leading arguments that make up the compose command
+    fn invocation(self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            ComposeCli::DockerPlugin => ("docker", &["compose"]),
+            ComposeCli::DockerCompose => ("docker-compose", &[]),
+            ComposeCli::Podman => ("podman", &["compose"]),
+            ComposeCli::PodmanCompose => ("podman-compose", &[]),
+            ComposeCli::Nerdctl => ("nerdctl", &["compose"]),
+        }
+    }
+
+    /// The container engine CLI behind this compose, for image queries
+    fn engine(self) -> &'static str {
+        match self {
+            ComposeCli::DockerPlugin | ComposeCli::DockerCompose => "docker",
+            ComposeCli::Podman | ComposeCli::PodmanCompose => "podman",
+            ComposeCli::Nerdctl => "nerdctl",
+        }
+    }
+
+    /// Human readable command name, e.g. `docker compose`
+    pub fn display(self) -> String {
+        let (program, prefix) = self.invocation();
+        std::iter::once(program)
+            .chain(prefix.iter().copied())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Start building a command for this CLI, aimed at `context` if given
+    fn command(self, context: Option<&str>) -> Command {
+        let (program, prefix) = self.invocation();
+        let mut command = Command::new(program);
+        command.args(prefix);
+        apply_context(&mut command, self.engine(), context);
+        command
+    }
+
+    /// The first compose CLI that answers `version`, detected once per run
+    pub fn detect() -> Result<ComposeCli, String> {
+        static DETECTED: OnceLock<Option<ComposeCli>> = OnceLock::new();
+        DETECTED
+            .get_or_init(|| {
+                ComposeCli::ALL.into_iter().find(|cli| {
+                    cli.command(None)
+                        .arg("version")
+                        .stdin(Stdio::null())
+                        .stdout(Stdio::null())
+                        .stderr(Stdio::null())
+                        .status()
+                        .is_ok_and(|status| status.success())
+                })
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No compose CLI found (tried {})",
+                    ComposeCli::ALL
+                        .iter()
+                        .map(|cli| cli.display())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+/// The variables that pick an engine's daemon: the context (or podman
+/// connection) name, and the host address that would otherwise win over it
+fn context_vars(engine: &str) -> Option<(&'static str, &'static str)> {
+    match engine {
+        "docker" => Some(("DOCKER_CONTEXT", "DOCKER_HOST")),
+        "podman" => Some(("CONTAINER_CONNECTION", "CONTAINER_HOST")),
+        _ => None,
+    }
+}
+
+/// Point a command at the context chosen with `--context`, which takes
+/// precedence over a `DOCKER_HOST` inherited from the environment
+fn apply_context(command: &mut Command, engine: &str, context: Option<&str>) {
+    if let (Some(context), Some((context_var, host_var))) = (context, context_vars(engine)) {
+        command.env(context_var, context).env_remove(host_var);
+    }
+}
+
+/// A container engine command (e.g. `docker`) aimed at `context` if given
+pub fn container_engine(engine: &str, context: Option<&str>) -> Command {
+    let mut command = Command::new(engine);
+    apply_context(&mut command, engine, context);
+    command
+}
+
+/// Which daemon an engine's commands go to, for history: `context: <name>`
+/// from `--context` or the environment, or `host: <address>`. `None` means
+/// the default daemon.
+pub fn context_note(engine: &str, context: Option<&str>) -> Option<String> {
+    let (context_var, host_var) = context_vars(engine)?;
+    let from_env = |var| env::var(var).ok().filter(|v| !v.is_empty());
+    if let Some(context) = context
+        .map(str::to_string)
+        .or_else(|| from_env(context_var))
+    {
+        return Some(format!("context: {}", context));
+    }
+    from_env(host_var).map(|host| format!("host: {}", host))
+}
+
+/// How a compose stack or service is restarted by `tickle`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComposeStrategy {
+    /// `compose restart`: restart the existing containers in place
+    Restart,
+    /// `compose up -d --force-recreate`: recreate containers, keep networks
+    Recreate,
+    /// `compose down` then `compose up -d`
+    DownUp,
+    /// `compose up -d --build`: rebuild images and recreate what changed
+    Build,
+    /// Start new containers next to the old ones, wait for them to be
+    /// healthy, then remove the old ones (named services only)
+    Rolling,
+}
+
+impl ComposeStrategy {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "restart" => Ok(ComposeStrategy::Restart),
+            "recreate" => Ok(ComposeStrategy::Recreate),
+            "down-up" => Ok(ComposeStrategy::DownUp),
+            "build" => Ok(ComposeStrategy::Build),
+            "rolling" => Ok(ComposeStrategy::Rolling),
+            other => Err(format!(
+                "unknown compose strategy '{}' (expected restart, recreate, down-up, build or rolling)",
+                other
+            )),
+        }
+    }
+}
+
+/// Compose settings from the command line, passed to every compose invocation
+#[derive(Debug, Clone, Default)]
+pub struct ComposeOptions {
+    /// Extra compose files (`--file`), applied after the project's own
+    pub files: Vec<String>,
+    /// Profiles to enable (`--profile`), in addition to `COMPOSE_PROFILES`
+    pub profiles: Vec<String>,
+    pub env_file: Option<String>,
+    pub project_name: Option<String>,
+    /// How to tickle; defaults depend on whether services were named
+    pub strategy: Option<ComposeStrategy>,
+    /// Pull (and for the build strategy, build) images before restarting
+    pub pull: bool,
+    /// Wait this long for containers to be running and healthy after
+    /// starting them; `None` returns as soon as compose does
+    pub wait: Option<Duration>,
+    /// Docker context (`--context`) for every compose and engine command
+    pub context: Option<String>,
+}
+
+/// One container as reported by `compose ps`
+struct ContainerStatus {
+    name: String,
+    service: String,
+    state: String,
+    /// Docker healthcheck status; empty without a healthcheck
+    health: String,
+    /// Human readable status, e.g. `Up 2 hours (healthy)`
+    summary: String,
+    /// Published ports, e.g. `0.0.0.0:8080->80/tcp`
+    ports: String,
+    /// Exit code of an exited container, when known
+    exit_code: Option<i32>,
+}
+
+impl ContainerStatus {
+    /// Running and healthy, or a one-shot container (a migration, an init
+    /// job) that finished successfully
+    fn ready(&self) -> bool {
+        (self.state == "running" && matches!(self.health.as_str(), "" | "healthy"))
+            || self.completed()
+    }
+
+    fn completed(&self) -> bool {
+        self.state == "exited" && self.exit_code == Some(0)
+    }
+
+    fn failed(&self) -> bool {
+        (self.state == "exited" && !self.completed())
+            || self.state == "dead"
+            || self.health == "unhealthy"
+    }
+
+    /// State plus health or exit code, e.g. `running, healthy`
+    fn status(&self) -> String {
+        match self.exit_code {
+            Some(code) if self.state == "exited" => format!("exited ({})", code),
+            _ if self.health.is_empty() => self.state.clone(),
+            _ => format!("{}, {}", self.state, self.health),
+        }
+    }
+
+    /// How long a running container has been up, e.g. `2 hours`
+    fn uptime(&self) -> Option<&str> {
+        let up = self.summary.strip_prefix("Up ")?;
+        Some(up.split(" (").next().unwrap_or(up))
+    }
+}
+
+/// Files listed in `COMPOSE_FILE`, made absolute against the CWD
+fn compose_file_env() -> Option<Vec<PathBuf>> {
+    let value = env::var("COMPOSE_FILE")
+        .ok()
+        .filter(|v| !v.trim().is_empty())?;
+    let separator = env::var("COMPOSE_PATH_SEPARATOR").unwrap_or_else(|_| ":".to_string());
+    let cwd = env::current_dir().ok()?;
+    Some(
+        value
+            .split(separator.as_str())
+            .filter(|file| !file.is_empty())
+            .map(|file| cwd.join(file))
+            .collect(),
+    )
+}
+
+/// The compose file in `dir` itself, if there is one
+pub fn compose_file_in(dir: &Path) -> Option<PathBuf> {
+    COMPOSE_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Return the absolute path of the nearest compose file, looking in the CWD
+/// and then its parents. The search stops at $HOME or the filesystem root.
+/// When `COMPOSE_FILE` is set, its first file is used instead.
+pub fn find_compose_file() -> Option<PathBuf> {
+    if let Some(files) = compose_file_env() {
+        return files.into_iter().next();
+    }
+    find_compose_file_from(&env::current_dir().ok()?)
+}
+
+/// The nearest compose file to `start`, searching like `find_compose_file`
+/// but ignoring `COMPOSE_FILE`, which is relative to the CWD
+pub fn find_compose_file_from(start: &Path) -> Option<PathBuf> {
+    search_up(start, compose_file_in)
+}
+
+/// The first thing `find` returns for `start` or one of its parents,
+/// stopping at $HOME or the filesystem root
+pub fn search_up<T>(start: &Path, mut find: impl FnMut(&Path) -> Option<T>) -> Option<T> {
+    let home = env::var_os("HOME").and_then(|home| PathBuf::from(home).canonicalize().ok());
+    for dir in start.ancestors() {
+        if let Some(found) = find(dir) {
+            return Some(found);
+        }
+        if home.as_deref() == Some(dir) {
+            break;
+        }
+    }
+    None
+}
+
+/// The override file compose would apply on top of `file` by convention,
+/// e.g. `docker-compose.override.yml`, if it exists in `dir`
+fn override_file(dir: &Path, file: &str) -> Option<String> {
+    let stem = file
+        .strip_suffix(".yml")
+        .or_else(|| file.strip_suffix(".yaml"))?;
+    [".override.yml", ".override.yaml"]
+        .iter()
+        .map(|suffix| format!("{}{}", stem, suffix))
+        .find(|name| dir.join(name).exists())
+}
+
+/// A compose stack, or a subset of its services. Compose commands run in
+/// the directory holding the compose file.
+pub struct ComposeStack<'a> {
+    dir: PathBuf,
+    file: String,
+    /// Global arguments (`-f`, `-p`, `--profile`, ...) for every invocation
+    global_args: Vec<String>,
+    /// Every compose file of the project, in override order
+    files: Vec<PathBuf>,
+    /// Explicit `--env-file`, relative to `dir`
+    env_file: Option<String>,
+    context: Option<String>,
+    strategy: Option<ComposeStrategy>,
+    pull: bool,
+    wait: Option<Duration>,
+    /// Outcome of the last pull, for the history entry
+    pull_note: RefCell<Option<String>>,
+    /// Services to act on; empty means the whole stack
+    services: Vec<String>,
+    out: &'a Output,
+    exec: ExecOptions,
+}
+
+impl<'a> ComposeStack<'a> {
+    pub fn new(path: &Path, services: Vec<String>, out: &'a Output, exec: ExecOptions) -> Self {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut stack = ComposeStack {
+            dir,
+            file,
+            global_args: Vec::new(),
+            files: Vec::new(),
+            env_file: None,
+            context: None,
+            strategy: None,
+            pull: false,
+            wait: None,
+            pull_note: RefCell::new(None),
+            services,
+            out,
+            exec,
+        };
+        stack.global_args = stack.build_global_args(&ComposeOptions::default());
+        stack
+    }
+
+    /// Apply command-line compose settings
+    pub fn with_options(mut self, options: &ComposeOptions) -> Self {
+        self.global_args = self.build_global_args(options);
+        self.env_file = options.env_file.clone();
+        self.context = options.context.clone();
+        self.strategy = options.strategy;
+        self.pull = options.pull;
+        self.wait = options.wait;
+        self
+    }
+
+    /// Compose files come from `COMPOSE_FILE` when it names this stack,
+    /// otherwise the compose file plus its override file; `--file` options
+    /// are added after either.
+    /// Profiles from `COMPOSE_PROFILES` are kept alongside `--profile`.
+    fn build_global_args(&mut self, options: &ComposeOptions) -> Vec<String> {
+        // Only the stack `COMPOSE_FILE` itself points at (the one found from
+        // the CWD) takes its files, not a `stack:` target or `-C` directory
+        let file = self.dir.join(&self.file);
+        let env_files = compose_file_env().filter(|files| files.first() == Some(&file));
+        let mut files = match env_files {
+            Some(files) => files.iter().map(|f| f.display().to_string()).collect(),
+            None => {
+                let mut files = vec![self.file.clone()];
+                files.extend(override_file(&self.dir, &self.file));
+                files
+            }
+        };
+        files.extend(options.files.iter().cloned());
+        self.files = files.iter().map(|file| self.dir.join(file)).collect();
+
+        let mut profiles: Vec<String> = env::var("COMPOSE_PROFILES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect();
+        for profile in &options.profiles {
+            if !profiles.contains(profile) {
+                profiles.push(profile.clone());
+            }
+        }
+
+        let mut args = Vec::new();
+        for file in files {
+            args.extend(["-f".to_string(), file]);
+        }
+        if let Some(name) = &options.project_name {
+            args.extend(["-p".to_string(), name.clone()]);
+        }
+        for profile in profiles {
+            args.extend(["--profile".to_string(), profile]);
+        }
+        if let Some(env_file) = &options.env_file {
+            args.extend(["--env-file".to_string(), env_file.clone()]);
+        }
+        args
+    }
+
+    /// Read the project's compose files natively, with variables from the
+    /// environment and the `.env` file (or `--env-file`)
+    pub fn project(&self) -> Result<ComposeProject, String> {
+        let env_file = match &self.env_file {
+            Some(env_file) => self.dir.join(env_file),
+            None => self.dir.join(".env"),
+        };
+        ComposeProject::load(&self.files, &env_file, self.env_file.is_some())
+    }
+
+    /// Check the compose files before changing anything, so a broken file
+    /// can't leave the stack down. Files using what tickle can't read itself
+    /// are checked by `compose config -q` instead, and give no project.
+    fn validate(&self) -> Result<Option<ComposeProject>, String> {
+        if let Some(reason) = compose_file::unsupported(&self.files) {
+            let cli = ComposeCli::detect()?;
+            self.out.line(format!(
+                "⚠️  Warning: {}, which tickle can't check itself; checking with `{} config -q`",
+                reason,
+                cli.display()
+            ));
+            self.query(&["config", "-q"])
+                .map_err(|e| format!("Invalid compose file {}: {}", self.path(), e))?;
+            return Ok(None);
+        }
+        let project = self.project()?;
+        for name in &project.unset_variables {
+            self.out.line(format!(
+                "⚠️  Warning: {} is not set; using an empty string",
+                name
+            ));
+        }
+        Ok(Some(project))
+    }
+
+    /// Full path of the compose file, for display
+    pub fn path(&self) -> String {
+        self.dir.join(&self.file).display().to_string()
+    }
+
+    /// Global arguments followed by `args`
+    fn full_args<'s>(&'s self, args: &[&'s str]) -> Vec<&'s str> {
+        self.global_args
+            .iter()
+            .map(String::as_str)
+            .chain(args.iter().copied())
+            .collect()
+    }
+
+    /// A compose command for the detected CLI with the global arguments and
+    /// `args`, run from the project directory
+    fn compose_command(&self, cli: ComposeCli, args: &[&str]) -> Command {
+        let mut command = cli.command(self.context.as_deref());
+        command.current_dir(&self.dir).args(self.full_args(args));
+        command
+    }
+
+    /// Run a read-only compose query and return its non-empty output lines
+    fn query(&self, args: &[&str]) -> Result<Vec<String>, String> {
+        let cli = ComposeCli::detect()?;
+        let output = self
+            .compose_command(cli, args)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", cli.display(), e))?;
+        if !output.status.success() {
+            return Err(error_detail(&output));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// List the services the compose project defines (`config --services`)
+    pub fn defined_services(&self) -> Result<Vec<String>, String> {
+        self.query(&["config", "--services"])
+    }
+
+    /// Local image ID for every image the project uses, `None` for one that
+    /// isn't present. `None` overall when the images can't be looked up.
+    fn image_ids(&self) -> Option<BTreeMap<String, Option<String>>> {
+        let cli = ComposeCli::detect().ok()?;
+        let images = self.query(&["config", "--images"]).ok()?;
+        images
+            .into_iter()
+            .map(|image| {
+                let output = container_engine(cli.engine(), self.context.as_deref())
+                    .args(["image", "inspect", "--format", "{{.Id}}", &image])
+                    .output()
+                    .ok()?;
+                let id = output
+                    .status
+                    .success()
+                    .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string());
+                Some((image, id))
+            })
+            .collect()
+    }
+
+    /// Status of the selected containers, including stopped ones. Only the
+    /// `docker compose` plugin understands this `ps --format` template; for
+    /// the other CLIs the container IDs are inspected through the engine.
+    fn containers(&self) -> Result<Vec<ContainerStatus>, String> {
+        let cli = ComposeCli::detect()?;
+        let lines = if cli == ComposeCli::DockerPlugin {
+            let mut args = vec![
+                "ps",
+                "-a",
+                "--format",
+                "{{.Name}}\t{{.Service}}\t{{.State}}\t{{.Health}}\t{{.Status}}\t{{.Ports}}\t{{.ExitCode}}",
+            ];
+            args.extend(self.services.iter().map(String::as_str));
+            self.query(&args)
+        } else {
+            self.inspect_containers(cli)
+        }
+        .map_err(|e| format!("Failed to check container status: {}", e))?;
+        Ok(lines
+            .iter()
+            .map(|line| {
+                let mut fields = line.split('\t').map(|f| f.trim().to_string());
+                ContainerStatus {
+                    name: fields
+                        .next()
+                        .unwrap_or_default()
+                        .trim_start_matches('/')
+                        .to_string(),
+                    service: fields.next().unwrap_or_default(),
+                    state: fields.next().unwrap_or_default(),
+                    health: fields.next().unwrap_or_default(),
+                    summary: fields.next().unwrap_or_default(),
+                    ports: fields.next().unwrap_or_default(),
+                    exit_code: fields.next().and_then(|code| code.parse().ok()),
+                }
+            })
+            .collect())
+    }
+
+    /// The fields of `containers` for compose CLIs without `ps --format`,
+    /// from `ps -q` and `container inspect`; there is no status summary or
+    /// port list
+    fn inspect_containers(&self, cli: ComposeCli) -> Result<Vec<String>, String> {
+        let mut args = vec!["ps", "-q"];
+        args.extend(self.services.iter().map(String::as_str));
+        let ids = self.query(&args)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let output = container_engine(cli.engine(), self.context.as_deref())
+            .args([
+                "container",
+                "inspect",
+                "--format",
+                "{{.Name}}\t{{index .Config.Labels \"com.docker.compose.service\"}}\t{{.State.Status}}\t{{if .State.Health}}{{.State.Health.Status}}{{end}}\t\t\t{{.State.ExitCode}}",
+            ])
+            .args(&ids)
+            .output()
+            .map_err(|e| format!("Failed to run {} container inspect: {}", cli.engine(), e))?;
+        if !output.status.success() {
+            return Err(error_detail(&output));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// How often the engine has restarted each container, by name
+    fn restart_counts(&self, containers: &[ContainerStatus]) -> BTreeMap<String, String> {
+        let Ok(cli) = ComposeCli::detect() else {
+            return BTreeMap::new();
+        };
+        if containers.is_empty() {
+            return BTreeMap::new();
+        }
+        let Ok(output) = container_engine(cli.engine(), self.context.as_deref())
+            .args([
+                "container",
+                "inspect",
+                "--format",
+                "{{.Name}} {{.RestartCount}}",
+            ])
+            .args(containers.iter().map(|c| &c.name))
+            .output()
+        else {
+            return BTreeMap::new();
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.trim().split_once(' '))
+            .map(|(name, count)| (name.trim_start_matches('/').to_string(), count.to_string()))
+            .collect()
+    }
+
+    /// Poll until every container is running (and healthy, if it has a
+    /// healthcheck), failing early when one exits or turns unhealthy
+    fn wait_until_ready(&self) -> Result<(), String> {
+        let Some(timeout) = self.wait else {
+            return Ok(());
+        };
+        if self.exec.dry_run {
+            return Ok(());
+        }
+
+        self.out.line(format!(
+            "⏳ Waiting up to {}s for containers to be running and healthy...",
+            timeout.as_secs()
+        ));
+        let deadline = Instant::now() + timeout;
+        loop {
+            let containers = self.containers()?;
+            let failed = containers.iter().filter(|c| c.failed()).count();
+            let ready = containers.iter().all(|c| c.ready());
+            if failed > 0 || ready || Instant::now() >= deadline {
+                if containers.is_empty() {
+                    self.out.line("⚠️  No containers found to wait for");
+                }
+                for c in &containers {
+                    let icon = if c.ready() {
+                        "✅"
+                    } else if c.failed() {
+                        "❌"
+                    } else {
+                        "⏳"
+                    };
+                    self.out.line(format!(
+                        "   {} {} ({}): {}",
+                        icon,
+                        c.name,
+                        c.service,
+                        c.status()
+                    ));
+                }
+                return if failed > 0 {
+                    Err(format!("{} container(s) unhealthy or exited", failed))
+                } else if ready {
+                    Ok(())
+                } else {
+                    Err(format!("containers not ready after {}s", timeout.as_secs()))
+                };
+            }
+            thread::sleep(WAIT_POLL);
+        }
+    }
+
+    /// Pull (and build) images while the stack is still running, so the
+    /// download isn't part of the downtime
+    fn pull_images(&self, strategy: ComposeStrategy) -> Result<(), String> {
+        let before = self.image_ids();
+
+        let mut args = vec!["pull"];
+        args.extend(self.services.iter().map(String::as_str));
+        self.out
+            .line(format!("📥 Pulling images for {}...", self.name()));
+        self.run_compose(&args)?;
+        if strategy == ComposeStrategy::Build {
+            args[0] = "build";
+            self.out
+                .line(format!("🔨 Building images for {}...", self.name()));
+            self.run_compose(&args)?;
+        }
+
+        if self.exec.dry_run {
+            return Ok(());
+        }
+        let note = match (before, self.image_ids()) {
+            (Some(before), Some(after)) => {
+                let changed: Vec<&str> = after
+                    .iter()
+                    .filter(|(image, id)| before.get(*image) != Some(id))
+                    .map(|(image, _)| image.as_str())
+                    .collect();
+                if changed.is_empty() {
+                    self.out.line("✅ Images already up to date");
+                    "images unchanged".to_string()
+                } else {
+                    self.out
+                        .line(format!("✅ Updated images: {}", changed.join(", ")));
+                    format!("images updated: {}", changed.join(", "))
+                }
+            }
+            _ => {
+                self.out
+                    .line("⚠️  Couldn't look up image IDs to tell what was updated");
+                "images: unknown".to_string()
+            }
+        };
+        *self.pull_note.borrow_mut() = Some(note);
+        Ok(())
+    }
+
+    /// Run a state-changing compose command with the detected CLI. A failure
+    /// is reported with that CLI's own error output.
+    fn run_compose(&self, args: &[&str]) -> Result<(), String> {
+        let cli = ComposeCli::detect()?;
+        let output = process::run(&mut self.compose_command(cli, args), self.exec, self.out)
+            .map_err(|e| format!("Failed to run {}: {}", cli.display(), e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} {} failed: {}",
+                cli.display(),
+                args.first().copied().unwrap_or_default(),
+                error_detail(&output)
+            ))
+        }
+    }
+
+    /// Perform `compose down` then `compose up -d` against the compose file.
+    fn down_up(&self) -> Result<(), String> {
+        self.out.line(format!(
+            "🐳 Compose file detected: {}. Performing `{} down`...",
+            self.path(),
+            ComposeCli::detect()?.display()
+        ));
+        self.run_compose(&["down"])?;
+        self.out
+            .line("🚀 Bringing stack back up in detached mode...");
+        self.run_compose(&["up", "-d"])?;
+        self.out.line("✅ Compose stack restarted.");
+        Ok(())
+    }
+
+    /// Replace a service's running containers without downtime: scale it
+    /// up with new containers, wait for those to be ready, then stop and
+    /// remove the old ones. If the new containers don't become ready they
+    /// are removed instead, leaving the old ones running.
+    fn roll_service(&self, service: &str) -> Result<(), String> {
+        let engine = ComposeCli::detect()?.engine();
+        let old = self.query(&["ps", "-q", service])?;
+        if old.is_empty() {
+            self.out.line(format!(
+                "▶️ No running {} containers to replace; starting {}...",
+                service, service
+            ));
+            return self.run_compose(&["up", "-d", "--no-deps", service]);
+        }
+
+        let scale = format!("{}={}", service, old.len() * 2);
+        self.out.line(format!(
+            "🐳 Starting {} new {} container(s) next to the old one(s)...",
+            old.len(),
+            service
+        ));
+        self.run_compose(&[
+            "up",
+            "-d",
+            "--no-deps",
+            "--no-recreate",
+            "--scale",
+            &scale,
+            service,
+        ])?;
+        if self.exec.dry_run {
+            self.out.line(format!(
+                "🔍 Would wait for the new {} container(s), then remove {}",
+                service,
+                old.join(" ")
+            ));
+            return Ok(());
+        }
+
+        let new: Vec<String> = self
+            .query(&["ps", "-q", service])?
+            .into_iter()
+            .filter(|id| !old.contains(id))
+            .collect();
+        if new.is_empty() {
+            return Err(format!("no new {} containers were started", service));
+        }
+        if let Err(e) = self.wait_for_containers(engine, &new) {
+            self.out.line(format!(
+                "↩️  Removing the new {} container(s); the old ones keep running",
+                service
+            ));
+            self.engine_command(engine, &["rm", "-f"], &new).ok();
+            return Err(format!("new {} container(s) not ready: {}", service, e));
+        }
+
+        self.out
+            .line(format!("🧹 Removing the old {} container(s)...", service));
+        self.engine_command(engine, &["stop"], &old)?;
+        self.engine_command(engine, &["rm"], &old)?;
+        self.out
+            .line(format!("✅ Replaced {} without downtime", service));
+        Ok(())
+    }
+
+    /// Poll containers by ID until all are ready, failing early when one
+    /// exits or turns unhealthy
+    fn wait_for_containers(&self, engine: &str, ids: &[String]) -> Result<(), String> {
+        let timeout = self.wait.unwrap_or(DEFAULT_WAIT);
+        self.out.line(format!(
+            "⏳ Waiting up to {}s for the new container(s) to be running and healthy...",
+            timeout.as_secs()
+        ));
+        let deadline = Instant::now() + timeout;
+        loop {
+            let output = container_engine(engine, self.context.as_deref())
+                .args([
+                    "container",
+                    "inspect",
+                    "--format",
+                    "{{.Name}} {{.State.Status}} {{if .State.Health}}{{.State.Health.Status}}{{end}}",
+                ])
+                .args(ids)
+                .output()
+                .map_err(|e| format!("Failed to run {} container inspect: {}", engine, e))?;
+            if !output.status.success() {
+                return Err(error_detail(&output));
+            }
+            let containers: Vec<ContainerStatus> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .map(|line| {
+                    let mut fields = line.split_whitespace().map(str::to_string);
+                    ContainerStatus {
+                        name: fields
+                            .next()
+                            .unwrap_or_default()
+                            .trim_start_matches('/')
+                            .to_string(),
+                        service: String::new(),
+                        state: fields.next().unwrap_or_default(),
+                        health: fields.next().unwrap_or_default(),
+                        summary: String::new(),
+                        ports: String::new(),
+                        exit_code: None,
+                    }
+                })
+                .collect();
+
+            let failed = containers.iter().find(|c| c.failed());
+            if let Some(c) = failed {
+                return Err(format!("{} is {}", c.name, c.status()));
+            }
+            if containers.iter().all(|c| c.ready()) {
+                for c in &containers {
+                    self.out.line(format!("   ✅ {}: {}", c.name, c.status()));
+                }
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!("not ready after {}s", timeout.as_secs()));
+            }
+            thread::sleep(WAIT_POLL);
+        }
+    }
+
+    /// Run `<engine> <args> <ids>`, e.g. `docker stop <id>...`
+    fn engine_command(&self, engine: &str, args: &[&str], ids: &[String]) -> Result<(), String> {
+        let output = process::run(
+            container_engine(engine, self.context.as_deref())
+                .args(args)
+                .args(ids),
+            self.exec,
+            self.out,
+        )
+        .map_err(|e| format!("Failed to run {} {}: {}", engine, args.join(" "), e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} {} failed: {}",
+                engine,
+                args.join(" "),
+                error_detail(&output)
+            ))
+        }
+    }
+
+    /// Run a compose subcommand against just the selected services
+    fn services_command(&self, verb: &str, args: &[&str]) -> Result<(), String> {
+        let list = self.services.join(", ");
+        self.out.line(format!(
+            "🐳 {} compose service(s) {} ({})...",
+            verb,
+            list,
+            self.path()
+        ));
+
+        let mut full_args = args.to_vec();
+        full_args.extend(self.services.iter().map(String::as_str));
+        self.run_compose(&full_args)?;
+        self.out
+            .line(format!("✅ Compose service(s) {} done.", list));
+        Ok(())
+    }
+}
+
+impl Target for ComposeStack<'_> {
+    fn name(&self) -> String {
+        if self.services.is_empty() {
+            format!("compose stack {}", self.path())
+        } else {
+            format!("compose service(s) {}", self.services.join(", "))
+        }
+    }
+
+    fn state(&self) -> Result<ServiceState, String> {
+        let cli = ComposeCli::detect()?;
+        let mut args = vec!["ps", "-q"];
+        args.extend(self.services.iter().map(String::as_str));
+        let output = self
+            .compose_command(cli, &args)
+            .output()
+            .map_err(|e| format!("Failed to check compose status: {}", e))?;
+        if !output.status.success() {
+            return Ok(ServiceState::Unknown);
+        }
+        if String::from_utf8_lossy(&output.stdout).trim().is_empty() {
+            Ok(ServiceState::Inactive)
+        } else {
+            Ok(ServiceState::Active)
+        }
+    }
+
+    /// An explicit compose strategy wins; otherwise a forced stop/start means
+    /// down/up, and the default is to recreate a whole stack's containers or
+    /// restart selected services in place
+    fn restart(&self, strategy: Option<RestartStrategy>) -> Result<(), String> {
+        let strategy = match (self.strategy, strategy) {
+            (Some(compose_strategy), _) => compose_strategy,
+            (None, Some(RestartStrategy::StopStart)) => ComposeStrategy::DownUp,
+            (None, Some(RestartStrategy::Restart)) => ComposeStrategy::Restart,
+            (None, None) if self.services.is_empty() => ComposeStrategy::Recreate,
+            (None, None) => ComposeStrategy::Restart,
+        };
+        let project = self.validate()?;
+        if strategy == ComposeStrategy::Rolling && self.services.is_empty() {
+            return Err(
+                "the rolling strategy needs service names, e.g. `tickle --compose-strategy rolling web`"
+                    .to_string(),
+            );
+        }
+        if self.pull {
+            self.pull_images(strategy)?;
+        }
+
+        let args: &[&str] = match strategy {
+            ComposeStrategy::DownUp if self.services.is_empty() => {
+                self.down_up()?;
+                return self.wait_until_ready();
+            }
+            ComposeStrategy::DownUp => {
+                self.services_command("Stopping", &["stop"])?;
+                self.services_command("Starting", &["up", "-d"])?;
+                return self.wait_until_ready();
+            }
+            ComposeStrategy::Rolling => {
+                let order = match &project {
+                    Some(project) => project.dependency_order(&self.services)?,
+                    None => self.services.clone(),
+                };
+                for service in order {
+                    self.roll_service(&service)?;
+                }
+                return self.wait_until_ready();
+            }
+            ComposeStrategy::Restart => &["restart"],
+            ComposeStrategy::Recreate => &["up", "-d", "--force-recreate"],
+            ComposeStrategy::Build => &["up", "-d", "--build"],
+        };
+        if self.services.is_empty() {
+            self.out.line(format!(
+                "🐳 Restarting compose stack {} ({} {})...",
+                self.path(),
+                ComposeCli::detect()?.display(),
+                args.join(" ")
+            ));
+            self.run_compose(args)?;
+            self.out.line("✅ Compose stack restarted.");
+        } else {
+            self.services_command("Restarting", args)?;
+        }
+        self.wait_until_ready()
+    }
+
+    /// Start compose stack
+    fn start(&self) -> Result<(), String> {
+        self.validate()?;
+        if self.services.is_empty() {
+            self.out
+                .line(format!("🐳 Starting compose stack: {}...", self.path()));
+            self.run_compose(&["up", "-d"])?;
+            self.out.line("✅ Compose stack started.");
+        } else {
+            self.services_command("Starting", &["up", "-d"])?;
+        }
+        self.wait_until_ready()
+    }
+
+    /// Stop compose stack
+    fn stop(&self) -> Result<(), String> {
+        if !self.services.is_empty() {
+            return self.services_command("Stopping", &["stop"]);
+        }
+        self.out
+            .line(format!("🐳 Stopping compose stack: {}...", self.path()));
+        self.run_compose(&["down"])?;
+        self.out.line("✅ Compose stack stopped.");
+        Ok(())
+    }
+
+    /// A table of every selected service's containers with their state,
+    /// health, uptime, restart count and published ports
+    fn print_status(&self) -> Result<(), String> {
+        let services = if self.services.is_empty() {
+            self.defined_services()?
+        } else {
+            self.services.clone()
+        };
+        let containers = self.containers()?;
+        let restarts = self.restart_counts(&containers);
+
+        println!("📊 Compose stack {}\n", self.path());
+        let mut rows = vec![[
+            "Service".to_string(),
+            "Container".to_string(),
+            "State".to_string(),
+            "Health".to_string(),
+            "Uptime".to_string(),
+            "Restarts".to_string(),
+            "Ports".to_string(),
+        ]];
+        let or_dash = |value: &str| {
+            if value.is_empty() {
+                "-".to_string()
+            } else {
+                value.to_string()
+            }
+        };
+        for service in &services {
+            let mut found = false;
+            for c in containers.iter().filter(|c| c.service == *service) {
+                found = true;
+                rows.push([
+                    service.clone(),
+                    c.name.clone(),
+                    c.state.clone(),
+                    or_dash(&c.health),
+                    or_dash(c.uptime().unwrap_or_default()),
+                    restarts
+                        .get(&c.name)
+                        .cloned()
+                        .unwrap_or_else(|| "?".to_string()),
+                    or_dash(&c.ports),
+                ]);
+            }
+            if !found {
+                rows.push(
+                    [service.as_str(), "-", "no container", "-", "-", "-", "-"].map(str::to_string),
+                );
+            }
+        }
+        output::print_table(&rows);
+        Ok(())
+    }
+
+    /// The daemon the stack runs on, and what `--pull` changed
+    fn history_note(&self) -> Option<String> {
+        let context = ComposeCli::detect()
+            .ok()
+            .and_then(|cli| context_note(cli.engine(), self.context.as_deref()));
+        let notes: Vec<String> = context
+            .into_iter()
+            .chain(self.pull_note.borrow().clone())
+            .collect();
+        (!notes.is_empty()).then(|| notes.join("; "))
+    }
+
+    /// `<compose> -f FILE logs`, limited to the selected services
+    fn logs_command(&self, options: &LogOptions) -> Result<Command, String> {
+        options.check_no_priority(&self.name())?;
+        let cli = ComposeCli::detect()?;
+        let mut command = self.compose_command(cli, &["logs"]);
+        docker_log_args(&mut command, options);
+        command.args(&self.services);
+        Ok(command)
+    }
+}
+
+/// Arguments shared by `docker logs` and `docker compose logs`
+pub fn docker_log_args(command: &mut Command, options: &LogOptions) {
+    if options.follow {
+        command.arg("-f");
+    }
+    if let Some(since) = &options.since {
+        command.args(["--since", &since.for_docker()]);
+    }
+    if let Some(until) = &options.until {
+        command.args(["--until", &until.for_docker()]);
+    }
+    if let Some(lines) = options.lines {
+        command.args(["--tail", &lines.to_string()]);
+    }
+}
+
+/// The most useful part of a failed command's output: stderr, else stdout
+fn error_detail(output: &std::process::Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.trim().is_empty() {
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    } else {
+        stderr.trim().to_string()
+    }
+}
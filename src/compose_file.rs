@@ -0,0 +1,575 @@
+// src/compose_file.rs
+//! Native reading of compose files: the services they define with their
+//! ports, dependencies and healthchecks, after `${VAR}` interpolation from
+//! the environment and the project's `.env` file. This lets tickle catch a
+//! broken compose file before it takes a stack down.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::output;
+use crate::yaml::{self, Value};
+
+/// One service of a compose project
+#[derive(Debug, Clone)]
+pub struct ServiceDef {
+    pub name: String,
+    pub image: Option<String>,
+    /// Build context, when the service is built locally
+    pub build: Option<String>,
+    /// Port mappings as written, e.g. `8080:80`
+    pub ports: Vec<String>,
+    pub depends_on: Vec<String>,
+    /// Healthcheck command, or `disabled`
+    pub healthcheck: Option<String>,
+}
+
+/// The merged services of a project's compose files
+#[derive(Debug)]
+pub struct ComposeProject {
+    pub services: Vec<ServiceDef>,
+    /// Variables that were used but not set anywhere
+    pub unset_variables: Vec<String>,
+}
+
+impl ComposeProject {
+    /// Read `files` (later files override earlier ones) and check them.
+    /// Variables come from the environment first, then `env_file`, which
+    /// must exist only if `env_file_required` is set.
+    pub fn load(
+        files: &[PathBuf],
+        env_file: &Path,
+        env_file_required: bool,
+    ) -> Result<Self, String> {
+        let mut variables = Variables {
+            dotenv: if env_file.exists() || env_file_required {
+                read_env_file(env_file)?
+            } else {
+                HashMap::new()
+            },
+            unset: Vec::new(),
+        };
+
+        if let Some(reason) = unsupported(files) {
+            return Err(format!(
+                "{}, which tickle can't read itself; `docker compose config` can show the project",
+                reason
+            ));
+        }
+
+        let mut merged = Value::Null;
+        for file in files {
+            let contents = fs::read_to_string(file)
+                .map_err(|e| format!("Failed to read {}: {}", file.display(), e))?;
+            let document = yaml::parse(&contents)
+                .and_then(|document| interpolate_value(document, &mut variables))
+                .map_err(|e| format!("{}: {}", file.display(), e))?;
+            merge(&mut merged, document);
+        }
+
+        let services = services(&merged).map_err(|problems| {
+            let names: Vec<String> = files.iter().map(|f| f.display().to_string()).collect();
+            format!(
+                "Invalid compose file {}:\n  - {}",
+                names.join(" + "),
+                problems.join("\n  - ")
+            )
+        })?;
+        Ok(ComposeProject {
+            services,
+            unset_variables: variables.unset,
+        })
+    }
+
+    pub fn service(&self, name: &str) -> Option<&ServiceDef> {
+        self.services.iter().find(|s| s.name == name)
+    }
+
+    /// `names` reordered so every service comes after the services it
+    /// depends on, directly or not; otherwise the given order is kept
+    pub fn dependency_order(&self, names: &[String]) -> Result<Vec<String>, String> {
+        dependency_order(&self.services, names)
+    }
+
+    /// Print the selected services (all when `names` is empty) as a table
+    pub fn print(&self, names: &[String]) -> Result<(), String> {
+        let mut rows = vec![[
+            "Service".to_string(),
+            "Image".to_string(),
+            "Ports".to_string(),
+            "Depends on".to_string(),
+            "Healthcheck".to_string(),
+        ]];
+        let selected: Vec<&ServiceDef> = if names.is_empty() {
+            self.services.iter().collect()
+        } else {
+            names
+                .iter()
+                .map(|name| {
+                    self.service(name)
+                        .ok_or_else(|| format!("No service named '{}' in the compose file", name))
+                })
+                .collect::<Result<_, _>>()?
+        };
+        for service in selected {
+            let image = match (&service.image, &service.build) {
+                (Some(image), _) => image.clone(),
+                (None, Some(context)) => format!("(build {})", context),
+                (None, None) => "-".to_string(),
+            };
+            let list = |items: &[String]| {
+                if items.is_empty() {
+                    "-".to_string()
+                } else {
+                    items.join(", ")
+                }
+            };
+            rows.push([
+                service.name.clone(),
+                image,
+                list(&service.ports),
+                list(&service.depends_on),
+                service
+                    .healthcheck
+                    .clone()
+                    .unwrap_or_else(|| "-".to_string()),
+            ]);
+        }
+
+        output::print_table(&rows);
+        Ok(())
+    }
+}
+
+/// Why `files` can't be read natively, if they use something only compose
+/// itself understands: `include:`, YAML tags such as `!reset`, or quoted
+/// strings over several lines
+pub fn unsupported(files: &[PathBuf]) -> Option<String> {
+    files.iter().find_map(|file| {
+        // Unreadable files are reported by `ComposeProject::load`
+        let contents = fs::read_to_string(file).ok()?;
+        let reason = if contents.lines().any(|line| line.starts_with("include:")) {
+            Some("`include:`".to_string())
+        } else {
+            yaml::unsupported(&contents)
+        };
+        reason.map(|reason| format!("{} uses {}", file.display(), reason))
+    })
+}
+
+/// Where interpolated variables come from
+struct Variables {
+    dotenv: HashMap<String, String>,
+    /// Names used without a value or default
+    unset: Vec<String>,
+}
+
+impl Variables {
+    /// The environment wins over the `.env` file
+    fn get(&self, name: &str) -> Option<String> {
+        env::var(name)
+            .ok()
+            .or_else(|| self.dotenv.get(name).cloned())
+    }
+
+    /// The value of a variable used without a default; unset is empty
+    fn get_or_warn(&mut self, name: &str) -> String {
+        self.get(name).unwrap_or_else(|| {
+            if !self.unset.iter().any(|v| v == name) {
+                self.unset.push(name.to_string());
+            }
+            String::new()
+        })
+    }
+}
+
+/// Parse a `.env` file of `KEY=value` lines
+fn read_env_file(path: &Path) -> Result<HashMap<String, String>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut vars = HashMap::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("{}: line {}: expected KEY=value", path.display(), i + 1))?;
+        let value = value.trim();
+        let value = if let Some(inner) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            inner.replace("\\n", "\n").replace("\\\"", "\"")
+        } else if let Some(inner) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+            inner.to_string()
+        } else {
+            match value.find(" #") {
+                Some(i) => value[..i].trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+        vars.insert(key.trim().to_string(), value);
+    }
+    Ok(vars)
+}
+
+/// Interpolate every string in a document
+fn interpolate_value(value: Value, vars: &mut Variables) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => Value::String(interpolate(&s, vars)?),
+        Value::Sequence(items) => Value::Sequence(
+            items
+                .into_iter()
+                .map(|item| interpolate_value(item, vars))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(entries) => Value::Mapping(
+            entries
+                .into_iter()
+                .map(|(k, v)| Ok((k, interpolate_value(v, vars)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Value::Null => Value::Null,
+    })
+}
+
+/// Expand `$VAR`, `${VAR}` and the `:-`, `-`, `:?`, `?`, `:+` and `+`
+/// forms the way compose does; `$$` is a literal `$`
+fn interpolate(text: &str, vars: &mut Variables) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(i) = rest.find('$') {
+        result.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = matching_brace(after)
+                .ok_or_else(|| format!("invalid interpolation format for '{}'", text))?;
+            result.push_str(&expand(&after[..end], vars)?);
+            rest = &after[end + 1..];
+        } else {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            if end == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+                result.push('$');
+            } else {
+                result.push_str(&vars.get_or_warn(&rest[..end]));
+                rest = &rest[end..];
+            }
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Index of the `}` closing an opened `${`, allowing nesting in defaults
+fn matching_brace(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(i),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Expand the inside of `${...}`
+fn expand(expression: &str, vars: &mut Variables) -> Result<String, String> {
+    let name_end = expression
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(expression.len());
+    let (name, operator) = expression.split_at(name_end);
+    if name.is_empty() {
+        return Err(format!(
+            "invalid interpolation format for '${{{}}}'",
+            expression
+        ));
+    }
+    let value = vars.get(name);
+    let set = value.is_some();
+    let non_empty = value.as_deref().is_some_and(|v| !v.is_empty());
+
+    let (op, argument) = match operator {
+        "" => return Ok(vars.get_or_warn(name)),
+        _ if operator.starts_with(":-")
+            || operator.starts_with(":?")
+            || operator.starts_with(":+") =>
+        {
+            operator.split_at(2)
+        }
+        _ if operator.starts_with(['-', '?', '+']) => operator.split_at(1),
+        _ => {
+            return Err(format!(
+                "invalid interpolation format for '${{{}}}'",
+                expression
+            ));
+        }
+    };
+    let present = if op.starts_with(':') { non_empty } else { set };
+    match op.trim_start_matches(':') {
+        "-" if present => Ok(value.unwrap_or_default()),
+        "-" => interpolate(argument, vars),
+        "?" if present => Ok(value.unwrap_or_default()),
+        "?" => Err(format!(
+            "required variable {} is missing a value: {}",
+            name,
+            interpolate(argument, vars)?
+        )),
+        _ if present => interpolate(argument, vars),
+        _ => Ok(String::new()),
+    }
+}
+
+/// Merge an override document into `base`: mappings merge key by key,
+/// sequences gain the new items, anything else is replaced
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.iter_mut().find(|(k, _)| *k == key) {
+                    Some((_, existing)) => merge(existing, value),
+                    None => base.push((key, value)),
+                }
+            }
+        }
+        (Value::Sequence(base), Value::Sequence(overlay)) => {
+            for item in overlay {
+                if !base.contains(&item) {
+                    base.push(item);
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Extract and check the services of a merged document, collecting every
+/// problem found
+fn services(document: &Value) -> Result<Vec<ServiceDef>, Vec<String>> {
+    let mut problems = Vec::new();
+    let entries: &[(String, Value)] = match document {
+        Value::Null => &[],
+        Value::Mapping(_) => match document.get("services") {
+            None | Some(Value::Null) => &[],
+            Some(Value::Mapping(entries)) => entries,
+            Some(other) => {
+                return Err(vec![format!(
+                    "services must be a mapping, not a {}",
+                    other.type_name()
+                )]);
+            }
+        },
+        other => {
+            return Err(vec![format!(
+                "the top level must be a mapping, not a {}",
+                other.type_name()
+            )]);
+        }
+    };
+
+    let mut services = Vec::new();
+    for (name, definition) in entries {
+        if definition.as_mapping().is_none() {
+            problems.push(format!("service '{}' must be a mapping", name));
+            continue;
+        }
+        let strings = |key: &str, problems: &mut Vec<String>| -> Vec<String> {
+            match definition.get(key) {
+                None | Some(Value::Null) => Vec::new(),
+                Some(Value::Sequence(items)) => items
+                    .iter()
+                    .filter_map(|item| match item {
+                        Value::String(s) => Some(s.clone()),
+                        // Long port syntax
+                        Value::Mapping(_) => {
+                            let field = |k| item.get(k).and_then(Value::as_str);
+                            match (field("published"), field("target")) {
+                                (Some(published), Some(target)) => {
+                                    Some(format!("{}:{}", published, target))
+                                }
+                                (None, Some(target)) => Some(target.to_string()),
+                                _ => {
+                                    problems.push(format!(
+                                        "service '{}' has a {} entry without a target",
+                                        name, key
+                                    ));
+                                    None
+                                }
+                            }
+                        }
+                        other => {
+                            problems.push(format!(
+                                "service '{}' has a {} {} entry",
+                                name,
+                                other.type_name(),
+                                key
+                            ));
+                            None
+                        }
+                    })
+                    .collect(),
+                // depends_on may map service names to conditions
+                Some(Value::Mapping(entries)) if key == "depends_on" => {
+                    entries.iter().map(|(k, _)| k.clone()).collect()
+                }
+                Some(other) => {
+                    problems.push(format!(
+                        "service '{}': {} must be a list, not a {}",
+                        name,
+                        key,
+                        other.type_name()
+                    ));
+                    Vec::new()
+                }
+            }
+        };
+
+        let image = definition
+            .get("image")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let build = match definition.get("build") {
+            Some(Value::String(context)) => Some(context.clone()),
+            Some(build @ Value::Mapping(_)) => Some(
+                build
+                    .get("context")
+                    .and_then(Value::as_str)
+                    .unwrap_or(".")
+                    .to_string(),
+            ),
+            _ => None,
+        };
+        if image.is_none() && build.is_none() && definition.get("extends").is_none() {
+            problems.push(format!(
+                "service '{}' has neither an image nor a build context",
+                name
+            ));
+        }
+
+        let ports = strings("ports", &mut problems);
+        for port in &ports {
+            if !valid_port(port) {
+                problems.push(format!("service '{}' has an invalid port '{}'", name, port));
+            }
+        }
+
+        services.push(ServiceDef {
+            name: name.clone(),
+            image,
+            build,
+            ports,
+            depends_on: strings("depends_on", &mut problems),
+            healthcheck: definition.get("healthcheck").and_then(healthcheck),
+        });
+    }
+
+    for service in &services {
+        for dependency in &service.depends_on {
+            if !services.iter().any(|s| s.name == *dependency) {
+                problems.push(format!(
+                    "service '{}' depends on undefined service '{}'",
+                    service.name, dependency
+                ));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        let all: Vec<String> = services.iter().map(|s| s.name.clone()).collect();
+        if let Err(cycle) = dependency_order(&services, &all) {
+            problems.push(cycle);
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(services)
+    } else {
+        Err(problems)
+    }
+}
+
+fn dependency_order(services: &[ServiceDef], names: &[String]) -> Result<Vec<String>, String> {
+    /// Depth-first: dependencies are placed before the service itself
+    fn visit(
+        name: &str,
+        services: &[ServiceDef],
+        names: &[String],
+        path: &mut Vec<String>,
+        done: &mut Vec<String>,
+        order: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if done.iter().any(|d| d == name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|p| p == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Err(format!("dependency cycle: {}", cycle.join(" -> ")));
+        }
+        path.push(name.to_string());
+        if let Some(service) = services.iter().find(|s| s.name == name) {
+            for dependency in &service.depends_on {
+                visit(dependency, services, names, path, done, order)?;
+            }
+        }
+        path.pop();
+        done.push(name.to_string());
+        if names.iter().any(|n| n == name) {
+            order.push(name.to_string());
+        }
+        Ok(())
+    }
+
+    let (mut path, mut done, mut order) = (Vec::new(), Vec::new(), Vec::new());
+    for name in names {
+        visit(name, services, names, &mut path, &mut done, &mut order)?;
+    }
+    Ok(order)
+}
+
+/// The command of a healthcheck, or `disabled`
+fn healthcheck(definition: &Value) -> Option<String> {
+    if definition.get("disable").and_then(Value::as_str) == Some("true") {
+        return Some("disabled".to_string());
+    }
+    match definition.get("test")? {
+        Value::String(command) => Some(command.trim().to_string()),
+        Value::Sequence(items) => {
+            let words: Vec<&str> = items.iter().filter_map(Value::as_str).collect();
+            match words.as_slice() {
+                ["NONE", ..] => Some("disabled".to_string()),
+                ["CMD" | "CMD-SHELL", rest @ ..] => Some(rest.join(" ")),
+                words => Some(words.join(" ")),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Short port syntax: `[[ip:]published:]target[/protocol]`, where each
+/// port may be a range
+fn valid_port(port: &str) -> bool {
+    let port = port.rsplit_once('/').map_or(port, |(port, _)| port);
+    let is_ports = |s: &str| {
+        s.split('-').count() <= 2
+            && s.split('-')
+                .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+    };
+    match port.rsplit_once(':') {
+        None => is_ports(port),
+        Some((host, target)) => {
+            let published = host
+                .rsplit_once(':')
+                .map_or(host, |(_, published)| published);
+            is_ports(target) && (published.is_empty() || is_ports(published))
+        }
+    }
+}
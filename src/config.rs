@@ -0,0 +1,290 @@
+// src/config.rs
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::RestartStrategy;
+use crate::custom::CustomType;
+use crate::regex::Regex;
+use crate::toml::{self, Table, Value};
+
+/// System-wide configuration, read first
+const SYSTEM_CONFIG: &str = "/etc/tickle/config.toml";
+
+/// Defaults for a systemd unit. Every field is optional so that values from
+/// the user config, the system config and the command line can be layered.
+#[derive(Debug, Clone, Default)]
+pub struct ServiceConfig {
+    pub strategy: Option<RestartStrategy>,
+    pub timeout: Option<u64>,
+    pub health_check: Option<String>,
+    pub follow: Option<bool>,
+    pub retries: Option<u32>,
+    pub protected: Option<bool>,
+    /// After an operation, print what the target logged during it
+    pub show_logs: Option<bool>,
+    /// After an operation, follow the new log lines until one matches this
+    pub ready_log: Option<String>,
+    /// How many seconds to wait for a `ready_log` line
+    pub ready_timeout: Option<u64>,
+    /// Fail when a new log line matches this
+    pub fail_log: Option<String>,
+}
+
+impl ServiceConfig {
+    /// Fill any unset fields from `other`
+    pub fn or(self, other: &ServiceConfig) -> ServiceConfig {
+        ServiceConfig {
+            strategy: self.strategy.or(other.strategy),
+            timeout: self.timeout.or(other.timeout),
+            health_check: self.health_check.or_else(|| other.health_check.clone()),
+            follow: self.follow.or(other.follow),
+            retries: self.retries.or(other.retries),
+            protected: self.protected.or(other.protected),
+            show_logs: self.show_logs.or(other.show_logs),
+            ready_log: self.ready_log.or_else(|| other.ready_log.clone()),
+            ready_timeout: self.ready_timeout.or(other.ready_timeout),
+            fail_log: self.fail_log.or_else(|| other.fail_log.clone()),
+        }
+    }
+
+    fn from_table(table: &Table, section: &str) -> Result<Self, String> {
+        let mut config = ServiceConfig::default();
+        for (key, value) in table {
+            let field = format!("{}.{}", section, key);
+            match key.as_str() {
+                "strategy" => {
+                    config.strategy = Some(match expect_str(value, &field)? {
+                        "restart" => RestartStrategy::Restart,
+                        "stop-start" => RestartStrategy::StopStart,
+                        other => {
+                            return Err(format!(
+                                "{}: unknown strategy '{}' (expected \"restart\" or \"stop-start\")",
+                                field, other
+                            ));
+                        }
+                    })
+                }
+                "timeout" => config.timeout = Some(expect_unsigned(value, &field)?),
+                "health_check" => {
+                    config.health_check = Some(expect_str(value, &field)?.to_string())
+                }
+                "follow" => config.follow = Some(expect_bool(value, &field)?),
+                "retries" => {
+                    config.retries = Some(
+                        u32::try_from(expect_unsigned(value, &field)?)
+                            .map_err(|_| format!("{}: value is too large", field))?,
+                    )
+                }
+                "protected" => config.protected = Some(expect_bool(value, &field)?),
+                "show_logs" => config.show_logs = Some(expect_bool(value, &field)?),
+                "ready_log" => config.ready_log = Some(expect_pattern(value, &field)?),
+                "ready_timeout" => config.ready_timeout = Some(expect_unsigned(value, &field)?),
+                "fail_log" => config.fail_log = Some(expect_pattern(value, &field)?),
+                _ => return Err(format!("unknown setting '{}'", field)),
+            }
+        }
+        Ok(config)
+    }
+}
+
+/// Settings loaded from `/etc/tickle/config.toml` and
+/// `~/.config/tickle/config.toml`, with the user file taking precedence.
+#[derive(Debug, Default)]
+pub struct Config {
+    defaults: ServiceConfig,
+    services: HashMap<String, ServiceConfig>,
+    /// Named lists of services, addressed on the command line as `@name`
+    groups: HashMap<String, Vec<String>>,
+    /// Custom target types, addressed as `<type>:<name>`
+    types: HashMap<String, CustomType>,
+}
+
+impl Config {
+    /// Load and merge the system and user config files. Missing files are
+    /// not an error; unreadable or malformed ones are.
+    pub fn load() -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut paths = vec![PathBuf::from(SYSTEM_CONFIG)];
+        paths.extend(user_config_path());
+
+        for path in paths {
+            if path.exists() {
+                let layer = Config::from_file(&path)?;
+                config.merge(layer);
+            }
+        }
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Config::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        let root = toml::parse(contents)?;
+        let mut config = Config::default();
+
+        for (key, value) in &root {
+            match key.as_str() {
+                "defaults" => {
+                    config.defaults =
+                        ServiceConfig::from_table(expect_table(value, "defaults")?, "defaults")?
+                }
+                "services" => {
+                    for (name, settings) in expect_table(value, "services")? {
+                        let section = format!("services.{}", name);
+                        let settings =
+                            ServiceConfig::from_table(expect_table(settings, &section)?, &section)?;
+                        config.services.insert(normalize_unit(name), settings);
+                    }
+                }
+                "groups" => {
+                    for (name, members) in expect_table(value, "groups")? {
+                        let field = format!("groups.{}", name);
+                        let members = members
+                            .as_array()
+                            .ok_or_else(|| {
+                                format!("{}: expected an array of service names", field)
+                            })?
+                            .iter()
+                            .map(|member| expect_str(member, &field).map(str::to_string))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        config.groups.insert(name.clone(), members);
+                    }
+                }
+                "types" => {
+                    for (name, templates) in expect_table(value, "types")? {
+                        let section = format!("types.{}", name);
+                        let kind =
+                            CustomType::from_table(expect_table(templates, &section)?, &section)?;
+                        config.types.insert(name.clone(), kind);
+                    }
+                }
+                _ => return Err(format!("unknown section '{}'", key)),
+            }
+        }
+        Ok(config)
+    }
+
+    /// Let the settings in `layer` override the ones already loaded
+    fn merge(&mut self, layer: Config) {
+        self.defaults = layer.defaults.or(&self.defaults);
+        for (name, settings) in layer.services {
+            let merged = match self.services.remove(&name) {
+                Some(existing) => settings.or(&existing),
+                None => settings,
+            };
+            self.services.insert(name, merged);
+        }
+        self.groups.extend(layer.groups);
+        self.types.extend(layer.types);
+    }
+
+    /// The custom target type called `name`, if one is configured
+    pub fn custom_type(&self, name: &str) -> Option<&CustomType> {
+        self.types.get(name)
+    }
+
+    /// Expand `@group` into its member services, in order. Members may name
+    /// other groups; each service appears at most once in the result.
+    pub fn expand_group(&self, group: &str) -> Result<Vec<String>, String> {
+        let mut services = Vec::new();
+        self.collect_group(group, &mut Vec::new(), &mut services)?;
+        Ok(services)
+    }
+
+    fn collect_group(
+        &self,
+        group: &str,
+        visiting: &mut Vec<String>,
+        services: &mut Vec<String>,
+    ) -> Result<(), String> {
+        if visiting.iter().any(|g| g == group) {
+            return Err(format!("Group @{} includes itself", group));
+        }
+        let members = self.groups.get(group).ok_or_else(|| {
+            let mut known: Vec<_> = self.groups.keys().map(|g| format!("@{}", g)).collect();
+            known.sort();
+            if known.is_empty() {
+                format!("Unknown group @{} (no groups are configured)", group)
+            } else {
+                format!(
+                    "Unknown group @{} (known groups: {})",
+                    group,
+                    known.join(", ")
+                )
+            }
+        })?;
+
+        visiting.push(group.to_string());
+        for member in members {
+            match member.strip_prefix('@') {
+                Some(nested) => self.collect_group(nested, visiting, services)?,
+                None if !services.contains(member) => services.push(member.clone()),
+                None => {}
+            }
+        }
+        visiting.pop();
+        Ok(())
+    }
+
+    /// Effective settings for a unit: its own section, then `[defaults]`
+    pub fn for_service(&self, service_name: &str) -> ServiceConfig {
+        let own = self
+            .services
+            .get(&normalize_unit(service_name))
+            .cloned()
+            .unwrap_or_default();
+        own.or(&self.defaults)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/tickle/config.toml`, falling back to `~/.config`
+fn user_config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("tickle").join("config.toml"))
+}
+
+/// `nginx` and `nginx.service` name the same unit
+fn normalize_unit(name: &str) -> String {
+    name.strip_suffix(".service").unwrap_or(name).to_string()
+}
+
+fn expect_table<'a>(value: &'a Value, field: &str) -> Result<&'a Table, String> {
+    value
+        .as_table()
+        .ok_or_else(|| format!("{}: expected a table, found {}", field, value.type_name()))
+}
+
+fn expect_str<'a>(value: &'a Value, field: &str) -> Result<&'a str, String> {
+    value
+        .as_str()
+        .ok_or_else(|| format!("{}: expected a string, found {}", field, value.type_name()))
+}
+
+/// A string that must be a valid regular expression
+fn expect_pattern(value: &Value, field: &str) -> Result<String, String> {
+    let pattern = expect_str(value, field)?;
+    Regex::new(pattern).map_err(|e| format!("{}: {}", field, e))?;
+    Ok(pattern.to_string())
+}
+
+fn expect_bool(value: &Value, field: &str) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("{}: expected a boolean, found {}", field, value.type_name()))
+}
+
+fn expect_unsigned(value: &Value, field: &str) -> Result<u64, String> {
+    value
+        .as_integer()
+        .and_then(|n| u64::try_from(n).ok())
+        .ok_or_else(|| format!("{}: expected a non-negative integer", field))
+}
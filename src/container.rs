@@ -0,0 +1,201 @@
+// src/container.rs
+//! Plain containers run directly by a container engine, without compose or
+//! systemd, addressed as `container:<name>` (docker) or `podman:<name>`.
+
+use std::process::{Command, Stdio};
+
+use crate::compose::{container_engine, context_note, docker_log_args};
+use crate::logs::LogOptions;
+use crate::output::Output;
+use crate::process::{self, ExecOptions};
+use crate::target::Target;
+use crate::{RestartStrategy, ServiceState};
+
+/// Whether `engine` (aimed at `context`, if given) has a container with
+/// this name
+pub fn exists(engine: &str, name: &str, context: Option<&str>) -> bool {
+    container_engine(engine, context)
+        .args(["container", "inspect", name])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// A container managed with `<engine> restart|start|stop <name>`
+pub struct ContainerTarget<'a> {
+    /// How the container was addressed, e.g. `container`
+    prefix: &'static str,
+    /// Engine CLI, e.g. `docker`
+    engine: &'static str,
+    name: String,
+    /// Docker context from `--context`
+    context: Option<String>,
+    out: &'a Output,
+    exec: ExecOptions,
+}
+
+impl<'a> ContainerTarget<'a> {
+    pub fn new(
+        prefix: &'static str,
+        engine: &'static str,
+        name: &str,
+        out: &'a Output,
+        exec: ExecOptions,
+    ) -> Self {
+        ContainerTarget {
+            prefix,
+            engine,
+            name: name.to_string(),
+            context: None,
+            out,
+            exec,
+        }
+    }
+
+    pub fn with_context(mut self, context: Option<&str>) -> Self {
+        self.context = context.map(str::to_string);
+        self
+    }
+
+    /// The engine CLI, aimed at the chosen context
+    fn command(&self) -> Command {
+        container_engine(self.engine, self.context.as_deref())
+    }
+
+    /// The container's status and healthcheck status (empty without one)
+    fn inspect(&self) -> Result<(String, String), String> {
+        let output = self
+            .command()
+            .args([
+                "container",
+                "inspect",
+                "--format",
+                "{{.State.Status}} {{if .State.Health}}{{.State.Health.Status}}{{end}}",
+                &self.name,
+            ])
+            .output()
+            .map_err(|e| format!("Failed to inspect container: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{}: {}",
+                self.name(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut fields = stdout.split_whitespace();
+        let status = fields.next().unwrap_or_default().to_string();
+        let health = fields.next().unwrap_or_default().to_string();
+        Ok((status, health))
+    }
+
+    /// Print the container's state and health, e.g. `running (healthy)`
+    fn report_state(&self, when: &str) {
+        let line = match self.inspect() {
+            Ok((status, health)) if health.is_empty() => {
+                format!("📊 {} state of {}: {}", when, self.name(), status)
+            }
+            Ok((status, health)) => {
+                format!(
+                    "📊 {} state of {}: {} ({})",
+                    when,
+                    self.name(),
+                    status,
+                    health
+                )
+            }
+            Err(e) => format!("⚠️  Warning: Could not inspect {}: {}", self.name(), e),
+        };
+        self.out.line(line);
+    }
+
+    /// Run `<engine> <action> <name>`
+    fn engine_command(&self, action: &str) -> Result<(), String> {
+        let output = process::run(
+            self.command().args([action, &self.name]),
+            self.exec,
+            self.out,
+        )
+        .map_err(|e| format!("Failed to run {} {}: {}", self.engine, action, e))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let detail = if stderr.trim().is_empty() {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        } else {
+            stderr.trim().to_string()
+        };
+        Err(format!("{} {} failed: {}", self.engine, action, detail))
+    }
+}
+
+impl Target for ContainerTarget<'_> {
+    fn name(&self) -> String {
+        format!("{}:{}", self.prefix, self.name)
+    }
+
+    fn state(&self) -> Result<ServiceState, String> {
+        let (status, _) = self.inspect()?;
+        Ok(match status.as_str() {
+            "running" => ServiceState::Active,
+            "created" | "exited" | "stopped" | "paused" | "configured" => ServiceState::Inactive,
+            "dead" => ServiceState::Failed,
+            _ => ServiceState::Unknown,
+        })
+    }
+
+    fn restart(&self, strategy: Option<RestartStrategy>) -> Result<(), String> {
+        self.report_state("Current");
+        if strategy == Some(RestartStrategy::StopStart) {
+            self.out.line(format!("🛑 Stopping {}...", self.name()));
+            self.engine_command("stop")?;
+            self.out.line(format!("▶️ Starting {}...", self.name()));
+            self.engine_command("start")?;
+        } else {
+            self.out
+                .line(format!("🔄 Attempting to restart {}...", self.name()));
+            self.engine_command("restart")?;
+        }
+        self.out
+            .line(format!("✅ Successfully restarted {}", self.name()));
+        self.report_state("New");
+        Ok(())
+    }
+
+    fn start(&self) -> Result<(), String> {
+        self.report_state("Current");
+        self.out.line(format!("▶️ Starting {}...", self.name()));
+        self.engine_command("start")?;
+        self.out
+            .line(format!("✅ Successfully started {}", self.name()));
+        self.report_state("New");
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        self.report_state("Current");
+        self.out.line(format!("🛑 Stopping {}...", self.name()));
+        self.engine_command("stop")?;
+        self.out
+            .line(format!("✅ Successfully stopped {}", self.name()));
+        self.report_state("New");
+        Ok(())
+    }
+
+    fn history_note(&self) -> Option<String> {
+        context_note(self.engine, self.context.as_deref())
+    }
+
+    /// `<engine> logs`, e.g. `docker logs -f --tail 100 <name>`
+    fn logs_command(&self, options: &LogOptions) -> Result<Command, String> {
+        options.check_no_priority(&self.name())?;
+        let mut command = self.command();
+        command.arg("logs");
+        docker_log_args(&mut command, options);
+        command.arg(&self.name);
+        Ok(command)
+    }
+}
@@ -0,0 +1,237 @@
+// src/custom.rs
+//! User-defined target types backed by command templates, for process
+//! managers tickle doesn't know about (pm2, supervisorctl, in-house scripts).
+//!
+//! ```toml
+//! [types.pm2]
+//! start = "pm2 start {name}"
+//! stop = "pm2 stop {name}"
+//! restart = "pm2 restart {name}"
+//! status = "pm2 describe {name}"
+//! logs = "pm2 logs {name}"
+//! ```
+//!
+//! Targets of such a type are addressed as `<type>:<name>`, e.g. `pm2:api`.
+
+use std::process::Command;
+
+use crate::logs::LogOptions;
+use crate::output::Output;
+use crate::process::{self, ExecOptions, shell_quote};
+use crate::target::Target;
+use crate::toml::{Table, Value};
+use crate::{RestartStrategy, ServiceState};
+
+/// Command templates for one custom target type. `{name}` is replaced with
+/// the (shell quoted) target name.
+#[derive(Debug, Clone)]
+pub struct CustomType {
+    start: String,
+    stop: String,
+    restart: Option<String>,
+    status: Option<String>,
+    logs: Option<String>,
+}
+
+impl CustomType {
+    pub fn from_table(table: &Table, section: &str) -> Result<Self, String> {
+        let mut templates = [None, None, None, None, None];
+        for (key, value) in table {
+            let slot = match key.as_str() {
+                "start" => 0,
+                "stop" => 1,
+                "restart" => 2,
+                "status" => 3,
+                "logs" => 4,
+                _ => return Err(format!("unknown setting '{}.{}'", section, key)),
+            };
+            match value {
+                Value::String(template) => templates[slot] = Some(template.clone()),
+                other => {
+                    return Err(format!(
+                        "{}.{}: expected a string, found {}",
+                        section,
+                        key,
+                        other.type_name()
+                    ));
+                }
+            }
+        }
+
+        let [start, stop, restart, status, logs] = templates;
+        Ok(CustomType {
+            start: start.ok_or_else(|| format!("{}: missing 'start' command", section))?,
+            stop: stop.ok_or_else(|| format!("{}: missing 'stop' command", section))?,
+            restart,
+            status,
+            logs,
+        })
+    }
+}
+
+/// A target of a custom type, e.g. `pm2:api`
+pub struct CustomTarget<'a> {
+    type_name: String,
+    name: String,
+    kind: CustomType,
+    out: &'a Output,
+    exec: ExecOptions,
+}
+
+impl<'a> CustomTarget<'a> {
+    pub fn new(
+        type_name: &str,
+        name: &str,
+        kind: CustomType,
+        out: &'a Output,
+        exec: ExecOptions,
+    ) -> Self {
+        CustomTarget {
+            type_name: type_name.to_string(),
+            name: name.to_string(),
+            kind,
+            out,
+            exec,
+        }
+    }
+
+    fn render(&self, template: &str) -> String {
+        template.replace("{name}", &shell_quote(&self.name))
+    }
+
+    /// Run one of the type's templates through `sh -c`
+    fn run_template(&self, action: &str, template: &str) -> Result<(), String> {
+        let command = self.render(template);
+        let output = process::run(
+            Command::new("sh").args(["-c", &command]),
+            self.exec,
+            self.out,
+        )
+        .map_err(|e| format!("Failed to execute {} command: {}", action, e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let detail = if stderr.trim().is_empty() {
+                stdout.trim().to_string()
+            } else {
+                stderr.trim().to_string()
+            };
+            Err(format!("{} failed: {}", capitalize(action), detail))
+        }
+    }
+}
+
+impl Target for CustomTarget<'_> {
+    fn name(&self) -> String {
+        format!("{}:{}", self.type_name, self.name)
+    }
+
+    /// Success of the `status` command means active; without one the state is unknown
+    fn state(&self) -> Result<ServiceState, String> {
+        let Some(template) = &self.kind.status else {
+            return Ok(ServiceState::Unknown);
+        };
+        let output = Command::new("sh")
+            .args(["-c", &self.render(template)])
+            .output()
+            .map_err(|e| format!("Failed to run status command: {}", e))?;
+        Ok(if output.status.success() {
+            ServiceState::Active
+        } else {
+            ServiceState::Inactive
+        })
+    }
+
+    fn restart(&self, strategy: Option<RestartStrategy>) -> Result<(), String> {
+        let state = self.state()?;
+        self.out
+            .line(format!("📊 Current state of {}: {:?}", self.name(), state));
+
+        match (&self.kind.restart, strategy) {
+            (Some(template), None | Some(RestartStrategy::Restart)) => {
+                self.out
+                    .line(format!("🔄 Attempting to restart {}...", self.name()));
+                self.run_template("restart", template)?;
+                self.out
+                    .line(format!("✅ Successfully restarted {}", self.name()));
+                Ok(())
+            }
+            _ => {
+                self.out.line(format!("🛑 Stopping {}...", self.name()));
+                self.run_template("stop", &self.kind.stop)?;
+                self.out.line(format!("▶️ Starting {}...", self.name()));
+                self.run_template("start", &self.kind.start)?;
+                self.out.line(format!(
+                    "✅ Successfully stopped and started {}",
+                    self.name()
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    fn start(&self) -> Result<(), String> {
+        self.out.line(format!("▶️ Starting {}...", self.name()));
+        self.run_template("start", &self.kind.start)?;
+        self.out
+            .line(format!("✅ Successfully started {}", self.name()));
+        Ok(())
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        self.out.line(format!("🛑 Stopping {}...", self.name()));
+        self.run_template("stop", &self.kind.stop)?;
+        self.out
+            .line(format!("✅ Successfully stopped {}", self.name()));
+        Ok(())
+    }
+
+    /// The type's `logs` command, which follows the logs; only `--grep`
+    /// (applied by tickle) can be combined with it
+    fn logs_command(&self, options: &LogOptions) -> Result<Command, String> {
+        let Some(template) = &self.kind.logs else {
+            return Err(format!(
+                "target type '{}' has no 'logs' command",
+                self.type_name
+            ));
+        };
+        if !options.follow {
+            return Err(format!(
+                "target type '{}' can only follow its logs, so --no-follow can't be used",
+                self.type_name
+            ));
+        }
+        if options.since.is_some()
+            || options.until.is_some()
+            || options.lines.is_some()
+            || options.priority.is_some()
+        {
+            return Err(format!(
+                "target type '{}' can't limit its logs by time, line count or priority",
+                self.type_name
+            ));
+        }
+        let mut command = Command::new("sh");
+        command.args(["-c", &self.render(template)]);
+        Ok(command)
+    }
+
+    /// The `logs` command can't start at a moment, so there's no telling
+    /// which lines came after the operation
+    fn new_logs(&self) -> Result<LogOptions, String> {
+        Err(format!(
+            "target type '{}' can't show only the logs since the operation; use -f to follow them",
+            self.type_name
+        ))
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
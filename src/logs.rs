@@ -0,0 +1,334 @@
+// src/logs.rs
+//! Showing a target's logs: `tickle logs` with its filters, and following
+//! them after an operation with `-f`
+
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio, exit};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::process;
+use crate::regex::Regex;
+use crate::target::Target;
+
+/// A `--since`/`--until` value: a time relative to now such as `10m`, or a
+/// timestamp passed to the log tool as given; or a moment tickle noted itself
+#[derive(Debug, Clone)]
+pub enum LogTime {
+    Ago(Duration),
+    At(String),
+    Exact(SystemTime),
+}
+
+impl LogTime {
+    /// `90s`, `10m`, `2h` and `1d` are relative; anything else is a timestamp
+    pub fn parse(value: &str) -> LogTime {
+        let units = [('s', 1), ('m', 60), ('h', 3600), ('d', 86400)];
+        for (suffix, secs) in units {
+            if let Some(n) = value
+                .strip_suffix(suffix)
+                .and_then(|n| n.parse::<u64>().ok())
+            {
+                return LogTime::Ago(Duration::from_secs(n * secs));
+            }
+        }
+        LogTime::At(value.to_string())
+    }
+
+    /// For `journalctl --since`, which takes relative times as `-600s` and
+    /// epoch seconds as `@1700000000`
+    pub fn for_journal(&self) -> String {
+        match self {
+            LogTime::Ago(ago) => format!("-{}s", ago.as_secs()),
+            LogTime::At(at) => at.clone(),
+            LogTime::Exact(time) => format!("@{}", epoch(*time).as_secs()),
+        }
+    }
+
+    /// For `docker logs --since`, which takes relative times as `600s` and
+    /// epoch seconds with a fraction
+    pub fn for_docker(&self) -> String {
+        match self {
+            LogTime::Ago(ago) => format!("{}s", ago.as_secs()),
+            LogTime::At(at) => at.clone(),
+            LogTime::Exact(time) => {
+                let since = epoch(*time);
+                format!("{}.{:09}", since.as_secs(), since.subsec_nanos())
+            }
+        }
+    }
+}
+
+fn epoch(time: SystemTime) -> Duration {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// Which logs to show
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub since: Option<LogTime>,
+    pub until: Option<LogTime>,
+    /// Show only the last N lines (before following)
+    pub lines: Option<usize>,
+    /// Only print lines matching this pattern; applied by tickle itself
+    pub grep: Option<String>,
+    /// journald priority, e.g. `err` or `0..4`
+    pub priority: Option<String>,
+    /// Only journal entries after this cursor
+    pub after_cursor: Option<String>,
+    pub follow: bool,
+}
+
+impl LogOptions {
+    /// Follow new lines, as `-f` does after an operation
+    pub fn follow() -> Self {
+        LogOptions {
+            follow: true,
+            ..LogOptions::default()
+        }
+    }
+
+    /// Only lines logged from now on
+    pub fn from_now() -> Self {
+        LogOptions {
+            since: Some(LogTime::Exact(SystemTime::now())),
+            ..LogOptions::default()
+        }
+    }
+
+    /// `--priority` only means something to journald
+    pub fn check_no_priority(&self, target: &str) -> Result<(), String> {
+        match self.priority {
+            Some(_) => Err(format!(
+                "--priority only applies to systemd units, not {}",
+                target
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Run a target's log command. Without `--grep` the log tool takes over the
+/// terminal; with a pattern, tickle reads its output and prints only the
+/// matching lines.
+pub fn show(target: &dyn Target, options: &LogOptions, dry_run: bool) -> Result<(), String> {
+    let grep = match &options.grep {
+        Some(pattern) => {
+            Some(Regex::new(pattern).map_err(|e| format!("Invalid --grep pattern: {}", e))?)
+        }
+        None => None,
+    };
+    let mut command = target.logs_command(options)?;
+    if dry_run {
+        println!("🔍 Would run: {}", process::describe(&command));
+        return Ok(());
+    }
+    if options.follow {
+        println!(
+            "📋 Following logs for {} (Ctrl+C to stop)...",
+            target.name()
+        );
+    }
+
+    let Some(grep) = grep else {
+        let err = command.exec();
+        return Err(format!("Failed to show logs: {}", err));
+    };
+    let mut stream = LogStream::spawn(&mut command)?;
+    for (source, line) in stream.lines.iter() {
+        if grep.is_match(&line) {
+            source.print(&line);
+        }
+    }
+    let status = stream.wait()?;
+    if !status.success() {
+        return Err(format!(
+            "{} failed ({})",
+            process::describe(&command),
+            status
+        ));
+    }
+    Ok(())
+}
+
+/// How long to wait for a `ready` line when no `--ready-timeout` is given
+pub const DEFAULT_READY_WAIT: Duration = Duration::from_secs(60);
+
+/// What to look for in the lines a target logs after an operation
+pub struct LogWatch<'a> {
+    /// Stop successfully at the first line matching this
+    pub ready: Option<&'a str>,
+    /// Fail at the first line matching this
+    pub fail: Option<&'a str>,
+    /// How long to wait for a `ready` line
+    pub timeout: Duration,
+}
+
+/// After an operation, print what the target logged since `since` was
+/// taken (see `Target::new_logs`). With a `ready` pattern, keep following
+/// until a line matches it and return that line; a line matching `fail`,
+/// the timeout, or the log command ending first are errors.
+pub fn show_new(
+    target: &dyn Target,
+    mut since: LogOptions,
+    watch: &LogWatch,
+    dry_run: bool,
+) -> Result<Option<String>, String> {
+    let compile = |pattern: Option<&str>, what: &str| {
+        pattern
+            .map(|p| Regex::new(p).map_err(|e| format!("Invalid {} pattern: {}", what, e)))
+            .transpose()
+    };
+    let ready = compile(watch.ready, "ready_log")?;
+    let fail = compile(watch.fail, "fail_log")?;
+    since.follow |= ready.is_some();
+    let mut command = target.logs_command(&since)?;
+    if dry_run {
+        println!("🔍 Would run: {}", process::describe(&command));
+        return Ok(None);
+    }
+    match watch.ready {
+        Some(pattern) => println!(
+            "📋 Logs for {} since the operation, until a line matches '{}' (up to {}s):",
+            target.name(),
+            pattern,
+            watch.timeout.as_secs()
+        ),
+        None => println!("📋 Logs for {} since the operation:", target.name()),
+    }
+
+    let mut stream = LogStream::spawn(&mut command)?;
+    let deadline = Instant::now() + watch.timeout;
+    loop {
+        let next = match ready {
+            Some(_) => stream
+                .lines
+                .recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => stream
+                .lines
+                .recv()
+                .map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match next {
+            Ok((source, line)) => {
+                source.print(&line);
+                if fail.as_ref().is_some_and(|p| p.is_match(&line)) {
+                    return Err(format!("failure pattern matched: {}", line.trim()));
+                }
+                if ready.as_ref().is_some_and(|p| p.is_match(&line)) {
+                    println!("✅ {} is ready", target.name());
+                    return Ok(Some(line.trim().to_string()));
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                return Err(format!(
+                    "no log line matched '{}' within {}s",
+                    watch.ready.unwrap_or_default(),
+                    watch.timeout.as_secs()
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    let status = stream.wait()?;
+    match watch.ready {
+        Some(pattern) => Err(format!("logs ended before a line matched '{}'", pattern)),
+        None if !status.success() => Err(format!(
+            "{} failed ({})",
+            process::describe(&command),
+            status
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Which output of the log command a line came from
+#[derive(Clone, Copy)]
+enum Source {
+    Stdout,
+    /// Container engines pass the container's stderr through on stderr
+    Stderr,
+}
+
+impl Source {
+    fn print(self, line: &str) {
+        match self {
+            Source::Stdout => println!("{}", line),
+            Source::Stderr => eprintln!("{}", line),
+        }
+    }
+}
+
+/// A running log command whose output lines arrive on a channel. The
+/// command is killed when the stream is dropped.
+struct LogStream {
+    child: Child,
+    lines: Receiver<(Source, String)>,
+}
+
+impl LogStream {
+    fn spawn(command: &mut Command) -> Result<Self, String> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", process::describe(command), e))?;
+        let (sender, lines) = mpsc::channel();
+        let outputs: [(Source, Box<dyn Read + Send>); 2] = [
+            (
+                Source::Stdout,
+                Box::new(child.stdout.take().expect("stdout is piped")),
+            ),
+            (
+                Source::Stderr,
+                Box::new(child.stderr.take().expect("stderr is piped")),
+            ),
+        ];
+        for (source, output) in outputs {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(output);
+                let mut line = Vec::new();
+                while reader.read_until(b'\n', &mut line).is_ok_and(|n| n > 0) {
+                    let text = String::from_utf8_lossy(&line);
+                    let text = text.trim_end_matches(['\n', '\r']).to_string();
+                    if sender.send((source, text)).is_err() {
+                        return;
+                    }
+                    line.clear();
+                }
+            });
+        }
+        Ok(LogStream { child, lines })
+    }
+
+    /// Wait for the command to exit on its own
+    fn wait(&mut self) -> Result<ExitStatus, String> {
+        self.child
+            .wait()
+            .map_err(|e| format!("Failed to wait for logs: {}", e))
+    }
+}
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Follow a target's logs after an operation, then exit
+pub fn follow(target: &dyn Target, dry_run: bool) -> ! {
+    match show(target, &LogOptions::follow(), dry_run) {
+        Ok(()) => exit(0),
+        Err(e) => {
+            eprintln!("❌ Failed to follow logs: {}", e);
+            exit(1);
+        }
+    }
+}
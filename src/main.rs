@@ -1,11 +1,38 @@
 // src/main.rs
+use std::collections::HashMap;
 use std::env;
+use std::fmt;
 use std::fs::{self, OpenOptions};
 use std::io::Write;
-use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::process::{Command, exit};
-use std::time::SystemTime;
+use std::process::{Command, Stdio, exit};
+use std::time::{Duration, SystemTime};
+
+mod compose;
+mod compose_file;
+mod config;
+mod container;
+mod custom;
+mod logs;
+mod output;
+mod process;
+mod project;
+mod regex;
+mod runner;
+mod stacks;
+mod target;
+mod toml;
+mod yaml;
+
+use compose::{ComposeOptions, ComposeStack, ComposeStrategy, DEFAULT_WAIT};
+use config::{Config, ServiceConfig};
+use logs::{LogOptions, LogTime, LogWatch};
+use output::Output;
+use process::ExecOptions;
+use project::Project;
+use regex::Regex;
+use stacks::StackRegistry;
+use target::Target;
 
 #[derive(Debug)]
 enum ServiceState {
@@ -15,25 +42,62 @@ enum ServiceState {
     Unknown,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum RestartStrategy {
     Restart,
     StopStart,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum TickleCommand {
     Tickle,
     Start,
     Stop,
     History,
+    Stack,
+    Inspect,
+    Status,
+    Logs,
 }
 
-struct ServiceManager;
+impl TickleCommand {
+    /// Name used for this command in history entries
+    fn name(&self) -> &'static str {
+        match self {
+            TickleCommand::Tickle => "tickle",
+            TickleCommand::Start => "start",
+            TickleCommand::Stop => "stop",
+            TickleCommand::History => "history",
+            TickleCommand::Stack => "stack",
+            TickleCommand::Inspect => "inspect",
+            TickleCommand::Status => "status",
+            TickleCommand::Logs => "logs",
+        }
+    }
+}
+
+struct ServiceManager<'a> {
+    out: &'a Output,
+    /// Timeout and dry-run handling for start/stop/restart calls
+    exec: ExecOptions,
+}
 
-impl ServiceManager {
-    fn new() -> Self {
-        ServiceManager
+impl<'a> ServiceManager<'a> {
+    fn new(out: &'a Output) -> Self {
+        ServiceManager {
+            out,
+            exec: ExecOptions::default(),
+        }
+    }
+
+    fn with_exec(mut self, exec: ExecOptions) -> Self {
+        self.exec = exec;
+        self
+    }
+
+    /// Run a state-changing systemctl command, honoring timeout and dry-run
+    fn systemctl(&self, args: &[&str]) -> std::io::Result<std::process::Output> {
+        process::run(Command::new("systemctl").args(args), self.exec, self.out)
     }
 
     /// Check if systemctl is available
@@ -62,6 +126,52 @@ impl ServiceManager {
         }
     }
 
+    /// The Quadlet file (`.container`, `.pod`, ...) a unit is generated
+    /// from, if any
+    fn quadlet_source(&self, service_name: &str) -> Option<String> {
+        let output = Command::new("systemctl")
+            .args(["show", service_name, "--property=SourcePath"])
+            .output()
+            .ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let source = stdout.trim().strip_prefix("SourcePath=")?;
+        QUADLET_EXTENSIONS
+            .iter()
+            .any(|ext| source.ends_with(ext))
+            .then(|| source.to_string())
+    }
+
+    /// Read unit properties such as `ActiveState` with `systemctl show`
+    fn unit_properties(
+        &self,
+        service_name: &str,
+        properties: &[&str],
+    ) -> Result<HashMap<String, String>, String> {
+        let output = Command::new("systemctl")
+            .args(["show", service_name])
+            .arg(format!("--property={}", properties.join(",")))
+            .output()
+            .map_err(|e| format!("Failed to read unit properties: {}", e))?;
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect())
+    }
+
+    /// Whether systemd knows a unit by this name
+    fn unit_exists(&self, service_name: &str) -> bool {
+        Command::new("systemctl")
+            .args(["cat", service_name])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
     /// Check if a service can be restarted (exists and is enabled/available)
     fn can_restart_service(&self, service_name: &str) -> Result<bool, String> {
         // First check if the service unit exists
@@ -121,14 +231,15 @@ impl ServiceManager {
 
     /// Execute systemctl restart
     fn restart_service(&self, service_name: &str) -> Result<(), String> {
-        println!("🔄 Attempting to restart {}...", service_name);
+        self.out
+            .line(format!("🔄 Attempting to restart {}...", service_name));
 
-        let output = Command::new("systemctl")
-            .args(["restart", service_name])
-            .output()
+        let output = self
+            .systemctl(&["restart", service_name])
             .map_err(|e| format!("Failed to execute restart command: {}", e))?;
         if output.status.success() {
-            println!("✅ Successfully restarted {}", service_name);
+            self.out
+                .line(format!("✅ Successfully restarted {}", service_name));
             Ok(())
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -138,24 +249,25 @@ impl ServiceManager {
 
     /// Execute systemctl stop then start
     fn stop_start_service(&self, service_name: &str) -> Result<(), String> {
-        println!("🛑 Stopping {}...", service_name);
+        self.out.line(format!("🛑 Stopping {}...", service_name));
 
-        let stop_output = Command::new("systemctl")
-            .args(["stop", service_name])
-            .output()
+        let stop_output = self
+            .systemctl(&["stop", service_name])
             .map_err(|e| format!("Failed to execute stop command: {}", e))?;
         if !stop_output.status.success() {
             let stderr = String::from_utf8_lossy(&stop_output.stderr);
             return Err(format!("Stop failed: {}", stderr.trim()));
         }
-        println!("▶️ Starting {}...", service_name);
+        self.out.line(format!("▶️ Starting {}...", service_name));
 
-        let start_output = Command::new("systemctl")
-            .args(["start", service_name])
-            .output()
+        let start_output = self
+            .systemctl(&["start", service_name])
             .map_err(|e| format!("Failed to execute start command: {}", e))?;
         if start_output.status.success() {
-            println!("✅ Successfully stopped and started {}", service_name);
+            self.out.line(format!(
+                "✅ Successfully stopped and started {}",
+                service_name
+            ));
             Ok(())
         } else {
             let stderr = String::from_utf8_lossy(&start_output.stderr);
@@ -165,15 +277,15 @@ impl ServiceManager {
 
     /// Start a systemd service
     fn start_service(&self, service_name: &str) -> Result<(), String> {
-        println!("▶️ Starting {}...", service_name);
+        self.out.line(format!("▶️ Starting {}...", service_name));
 
-        let output = Command::new("systemctl")
-            .args(["start", service_name])
-            .output()
+        let output = self
+            .systemctl(&["start", service_name])
             .map_err(|e| format!("Failed to execute start command: {}", e))?;
 
         if output.status.success() {
-            println!("✅ Successfully started {}", service_name);
+            self.out
+                .line(format!("✅ Successfully started {}", service_name));
             Ok(())
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -183,15 +295,15 @@ impl ServiceManager {
 
     /// Stop a systemd service
     fn stop_service(&self, service_name: &str) -> Result<(), String> {
-        println!("🛑 Stopping {}...", service_name);
+        self.out.line(format!("🛑 Stopping {}...", service_name));
 
-        let output = Command::new("systemctl")
-            .args(["stop", service_name])
-            .output()
+        let output = self
+            .systemctl(&["stop", service_name])
             .map_err(|e| format!("Failed to execute stop command: {}", e))?;
 
         if output.status.success() {
-            println!("✅ Successfully stopped {}", service_name);
+            self.out
+                .line(format!("✅ Successfully stopped {}", service_name));
             Ok(())
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -199,20 +311,22 @@ impl ServiceManager {
         }
     }
 
-    /// Main tickle operation
-    fn tickle_service(&self, service_name: &str, force_stop_start: bool) -> Result<(), String> {
-        self.check_systemctl_available()?;
-
+    /// Main tickle operation. A `forced_strategy` skips capability detection.
+    fn tickle_service(
+        &self,
+        service_name: &str,
+        forced_strategy: Option<RestartStrategy>,
+    ) -> Result<(), String> {
         // Get current service state
         let state = self.get_service_state(service_name)?;
-        println!("📊 Current state of {}: {:?}", service_name, state);
+        self.out
+            .line(format!("📊 Current state of {}: {:?}", service_name, state));
 
-        let strategy = if force_stop_start {
-            RestartStrategy::StopStart
-        } else {
-            self.determine_restart_strategy(service_name)?
+        let strategy = match forced_strategy {
+            Some(strategy) => strategy,
+            None => self.determine_restart_strategy(service_name)?,
         };
-        println!("🎯 Using strategy: {:?}", strategy);
+        self.out.line(format!("🎯 Using strategy: {:?}", strategy));
 
         match strategy {
             RestartStrategy::Restart => self.restart_service(service_name),
@@ -221,8 +335,22 @@ impl ServiceManager {
     }
 }
 
+/// Quadlet file types that podman's systemd generator turns into units
+const QUADLET_EXTENSIONS: [&str; 7] = [
+    ".container",
+    ".pod",
+    ".kube",
+    ".network",
+    ".volume",
+    ".image",
+    ".build",
+];
+
 /* ------------------ History management ------------------ */
 
+/// Longest failure note kept in a history entry
+const MAX_NOTE_CHARS: usize = 160;
+
 struct HistoryManager {
     history_dir: PathBuf,
     history_file: PathBuf,
@@ -287,13 +415,49 @@ impl HistoryManager {
         }
     }
 
-    /// Log a command execution to history
-    fn log_command(&self, command: &str, target: &str, success: bool) -> Result<(), String> {
+    /// Note recorded for a failed command: the last line of its error,
+    /// which for a command's output tail is usually the most telling one
+    fn failure_note(error: &str) -> String {
+        let line = error
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default();
+        match line.char_indices().nth(MAX_NOTE_CHARS) {
+            Some((end, _)) => format!("{}...", &line[..end]),
+            None => line.to_string(),
+        }
+    }
+
+    /// Note for a finished operation: the target's own note, followed by
+    /// the tail of the error on failure
+    fn note_for(result: &Result<(), String>, target: &dyn Target) -> Option<String> {
+        let notes: Vec<String> = target
+            .history_note()
+            .into_iter()
+            .chain(result.as_ref().err().map(|e| Self::failure_note(e)))
+            .collect();
+        (!notes.is_empty()).then(|| notes.join("; "))
+    }
+
+    /// Log a command execution to history, with an optional trailing note
+    fn log_command(
+        &self,
+        command: &str,
+        target: &str,
+        success: bool,
+        note: Option<&str>,
+    ) -> Result<(), String> {
         self.ensure_directory()?;
 
         let timestamp = Self::get_timestamp();
         let status = if success { "SUCCESS" } else { "FAILED" };
-        let log_entry = format!("{} | {} | {} | {}\n", timestamp, command, target, status);
+        let mut log_entry = format!("{} | {} | {} | {}", timestamp, command, target, status);
+        if let Some(note) = note {
+            log_entry.push_str(&format!(" | {}", note));
+        }
+        log_entry.push('\n');
 
         let mut file = OpenOptions::new()
             .create(true)
@@ -364,121 +528,6 @@ impl HistoryManager {
     }
 }
 
-/* ------------------ Compose helpers ------------------ */
-
-/// Return the first compose file found in the CWD, if any.
-fn find_compose_file() -> Option<&'static str> {
-    // Check common names in a sensible order
-    let candidates = [
-        "docker-compose.yml",
-        "docker-compose.yaml",
-        "compose.yml",
-        "compose.yaml",
-        "container-compose.yml",
-        "container-compose.yaml",
-    ];
-    candidates
-        .into_iter()
-        .find(|&name| Path::new(name).exists())
-        .map(|v| v as _)
-}
-
-/// Try running `docker compose <args...>` first; fall back to `docker-compose <args...>`.
-fn run_compose_with_best_cli(args: &[&str]) -> Result<(), String> {
-    // Prefer modern `docker compose`
-    let try_docker_compose_plugin = Command::new("docker")
-        .args(std::iter::once("compose").chain(args.iter().copied()))
-        .output();
-    if let Ok(out) = try_docker_compose_plugin {
-        if out.status.success() {
-            return Ok(());
-        } else {
-            let stderr = String::from_utf8_lossy(&out.stderr);
-            // If the failure might be due to missing plugin, we'll try legacy next.
-            // Otherwise still try legacy for compatibility.
-            // println!("debug docker compose error: {}", stderr);
-            // fallthrough
-            if !stderr.is_empty() {
-                // continue to legacy attempt
-            }
-        }
-    }
-
-    // Legacy `docker-compose`
-    let legacy = Command::new("docker-compose")
-        .args(args)
-        .output()
-        .map_err(|e| format!("Failed to run docker-compose: {}", e))?;
-    if legacy.status.success() {
-        Ok(())
-    } else {
-        Err(format!(
-            "Compose command failed: {}",
-            String::from_utf8_lossy(&legacy.stderr).trim()
-        ))
-    }
-}
-
-/// Perform `compose down` then `compose up -d` against the given compose file.
-fn compose_down_up(compose_file: &str) -> Result<(), String> {
-    println!(
-        "🐳 Compose file detected: {}. Performing `docker compose down`...",
-        compose_file
-    );
-    run_compose_with_best_cli(&["-f", compose_file, "down"])?;
-    println!("🚀 Bringing stack back up in detached mode...");
-    run_compose_with_best_cli(&["-f", compose_file, "up", "-d"])?;
-    println!("✅ Compose stack restarted.");
-    Ok(())
-}
-
-/// Start compose stack
-fn compose_start(compose_file: &str) -> Result<(), String> {
-    println!("🐳 Starting compose stack: {}...", compose_file);
-    run_compose_with_best_cli(&["-f", compose_file, "up", "-d"])?;
-    println!("✅ Compose stack started.");
-    Ok(())
-}
-
-/// Stop compose stack
-fn compose_stop(compose_file: &str) -> Result<(), String> {
-    println!("🐳 Stopping compose stack: {}...", compose_file);
-    run_compose_with_best_cli(&["-f", compose_file, "down"])?;
-    println!("✅ Compose stack stopped.");
-    Ok(())
-}
-
-/* ------------------ Log following ------------------ */
-
-/// Replace the current process with `docker compose -f FILE logs -f`.
-/// Tries `docker compose` first, falls back to `docker-compose`.
-fn follow_compose_logs(compose_file: &str) -> ! {
-    println!("📋 Following compose logs (Ctrl+C to stop)...");
-    let err = Command::new("docker")
-        .args(["compose", "-f", compose_file, "logs", "-f"])
-        .exec();
-    // exec() only returns on failure — try legacy CLI
-    eprintln!(
-        "⚠️  docker compose not available ({}), trying docker-compose...",
-        err
-    );
-    let err = Command::new("docker-compose")
-        .args(["-f", compose_file, "logs", "-f"])
-        .exec();
-    eprintln!("❌ Failed to follow logs: {}", err);
-    exit(1);
-}
-
-/// Replace the current process with `journalctl -f -u SERVICE`.
-fn follow_service_logs(service_name: &str) -> ! {
-    println!("📋 Following logs for {} (Ctrl+C to stop)...", service_name);
-    let err = Command::new("journalctl")
-        .args(["-f", "-u", service_name])
-        .exec();
-    eprintln!("❌ Failed to follow logs: {}", err);
-    exit(1);
-}
-
 /* ------------------ CLI / UX ------------------ */
 
 fn print_version() {
@@ -486,18 +535,66 @@ fn print_version() {
 }
 
 fn print_usage() {
-    println!("Usage: tickle [COMMAND] [OPTIONS] [service_name]");
+    println!("Usage: tickle [COMMAND] [OPTIONS] [service_name...]");
     println!();
     println!("COMMANDS:");
     println!("  start               Start a service or compose stack");
     println!("  stop                Stop a service or compose stack");
     println!("  history             Show command history");
     println!("  history clear       Clear command history");
+    println!("  inspect [service...] Show the compose file's services, ports, dependencies");
+    println!("                      and healthchecks");
+    println!("  status [service...] Show the state of services or the compose stack's");
+    println!("                      containers without changing anything");
+    println!("  logs [service]      Show (and follow) the logs of a service or compose stack");
+    println!("  stack add <name> <dir>  Register a compose project as stack:<name>");
+    println!("  stack list          Show registered stacks and their state");
+    println!("  stack remove <name> Forget a registered stack");
     println!("  (default)           Restart/tickle a service or compose stack");
     println!();
     println!("OPTIONS:");
     println!("  -f, --follow        Follow logs after the operation completes");
     println!("  -s, --stop-start    Force stop/start instead of restart (tickle only)");
+    println!("  --strategy <name>   Force a strategy: restart or stop-start (tickle only)");
+    println!("  --timeout <secs>    Kill and fail any command tickle runs (systemctl, compose,");
+    println!("                      health checks, custom and project commands) after this long");
+    println!("  --retries <N>       Retry a failed operation up to N more times");
+    println!("  --health-check <cmd> Run a shell command after start/tickle; fail if it fails");
+    println!("  --no-follow         Don't follow logs, even if the config asks for it");
+    println!("  --show-logs         After the operation, print only what the service logged");
+    println!("                      during and after it");
+    println!("  --until-log <regex> Follow the new log lines until one matches, then exit 0;");
+    println!("                      fail after --ready-timeout seconds");
+    println!("  --ready-timeout <secs> How long --until-log waits for a match (default: 60)");
+    println!("  --fail-log <regex>  Show the new log lines; fail as soon as one matches");
+    println!("  --force             Operate on services marked as protected");
+    println!("  --dry-run           Show the commands that would run without running them");
+    println!("  -q, --quiet         Don't stream the output of the commands tickle runs");
+    println!(
+        "  --systemd           Treat names as systemd units even if a compose service matches"
+    );
+    println!("  --parallel <N>      Operate on up to N services at once (default: 1)");
+    println!("  --compose-strategy <name>  How to tickle compose: restart, recreate,");
+    println!("                      down-up, build or rolling (tickle only)");
+    println!("  --pull              Pull compose images before restarting (tickle only)");
+    println!("  --wait-timeout <secs> Wait this long for compose containers to be healthy");
+    println!("                      after a start or tickle (default: 60)");
+    println!("  --no-wait           Don't wait for compose containers to be healthy");
+    println!("  --file <path>       Add a compose file (after COMPOSE_FILE or the override file)");
+    println!("  --profile <name>    Enable a compose profile (adds to COMPOSE_PROFILES)");
+    println!("  --env-file <path>   Pass an env file to compose");
+    println!("  -p, --project-name <name>  Use this compose project name");
+    println!("  --context <name>    Docker context for compose and container commands");
+    println!("                      (overrides DOCKER_CONTEXT and DOCKER_HOST)");
+    println!("  @<group>            Operate on every service of a group from the config");
+    println!("  -C <dir>            Run as if tickle was started in <dir>. Repeat it, or give");
+    println!("                      several directory paths (-C ~/stacks/*), to handle each");
+    println!("                      directory's compose project with a summary at the end");
+    println!("  --since <time>      Logs since a time: 10m, 2h, 1d or a timestamp (logs only)");
+    println!("  --until <time>      Logs up to a time, in the same forms (logs only)");
+    println!("  --lines <N>         Show only the last N log lines (logs only)");
+    println!("  --grep <regex>      Show only log lines matching the pattern (logs only)");
+    println!("  --priority <level>  Journal priority, e.g. err or 0..4 (logs only, systemd)");
     println!("  -n <lines>          Show last N lines of history (with history command)");
     println!("  -v, --version       Show version information");
     println!("  -h, --help          Show this help message");
@@ -505,16 +602,46 @@ fn print_usage() {
     println!("Behavior:");
     println!("  • If run in a directory containing a compose file (docker-compose.yml/.yaml,");
     println!("    compose.yml/.yaml, container-compose.yml/.yaml) and no <service_name> is");
-    println!("    provided, tickle will operate on the compose stack:");
-    println!("        tickle          -> docker compose down && docker compose up -d");
+    println!("    provided, tickle will operate on the compose stack. Parent directories are");
+    println!("    searched up to $HOME, and compose runs from the compose file's directory:");
+    println!("        tickle          -> docker compose up -d --force-recreate");
     println!("        tickle start    -> docker compose up -d");
     println!("        tickle stop     -> docker compose down");
     println!();
+    println!("  • In a compose project directory, a name that matches a compose service");
+    println!("    (docker compose config --services) operates on just that service:");
+    println!("        tickle web         -> docker compose restart web");
+    println!("        tickle start web   -> docker compose up -d web");
+    println!("        tickle stop web    -> docker compose stop web");
+    println!("    Use --systemd to act on a systemd unit of the same name instead.");
+    println!();
     println!("  • Otherwise, tickle will operate on the named systemd service:");
     println!("        tickle nginx    -> systemctl restart nginx (or stop+start if needed)");
     println!("        tickle start nginx -> systemctl start nginx");
     println!("        tickle stop nginx  -> systemctl stop nginx");
     println!();
+    println!("  • <type>:<name> addresses a target of a custom type defined in the config,");
+    println!("    e.g. pm2:api runs the [types.pm2] command templates for \"api\".");
+    println!("    container:<name> and podman:<name> address standalone containers; a name");
+    println!("    with no systemd unit is also tried as a docker container.");
+    println!();
+    println!("  • stack:<name> addresses a compose project registered with `tickle stack");
+    println!("    add`, so it can be tickled, stopped or followed from any directory.");
+    println!();
+    println!("  • web.container and db.pod name the units Quadlet generates from those");
+    println!("    files (web.service, db-pod.service).");
+    println!();
+    println!("  • A .tickle.toml file in the current directory or a parent (up to $HOME)");
+    println!("    defines the ordered steps (compose stacks, systemd units, commands) that");
+    println!("    make up the project. It takes precedence over a compose file in the same");
+    println!("    directory; a compose file in a closer directory wins.");
+    println!();
+    println!("  • Several services may be named at once. Each is handled in turn (or N at a");
+    println!("    time with --parallel N) and a summary table is printed at the end.");
+    println!();
+    println!("  • Per-service defaults are read from /etc/tickle/config.toml and");
+    println!("    ~/.config/tickle/config.toml. Command line options override them.");
+    println!();
     println!("  • History is stored in ~/.tickle/history.log");
     println!();
     println!("Examples:");
@@ -522,6 +649,11 @@ fn print_usage() {
     println!("  tickle start apache2");
     println!("  tickle stop postgresql");
     println!("  tickle --stop-start apache2");
+    println!("  tickle --parallel 4 nginx php8.2-fpm varnish redis");
+    println!("  tickle @web                 # every service in the \"web\" group");
+    println!("  tickle stop @web");
+    println!("  tickle pm2:api              # custom target type from the config");
+    println!("  tickle --dry-run nginx      # show what would be run");
     println!("  tickle history              # Show full history");
     println!("  tickle history -n 10        # Show last 10 entries");
     println!("  tickle history clear        # Clear all history");
@@ -532,6 +664,472 @@ fn print_usage() {
     println!("  tickle -f                   # restart compose stack then follow logs");
 }
 
+/// Options for the tickle, start and stop commands
+struct CliOptions {
+    /// Settings given on the command line; these win over config files
+    overrides: ServiceConfig,
+    force: bool,
+    dry_run: bool,
+    /// Don't stream the output of the commands tickle runs
+    quiet: bool,
+    /// Never treat names as compose services
+    force_systemd: bool,
+    /// Files, profiles etc. passed to every compose invocation
+    compose: ComposeOptions,
+    /// Filters for the logs command
+    logs: LogOptions,
+    parallel: usize,
+    targets: Vec<String>,
+}
+
+impl CliOptions {
+    /// How commands run for a target with the given effective settings
+    fn exec(&self, settings: &ServiceConfig) -> ExecOptions {
+        ExecOptions {
+            timeout: settings.timeout.map(Duration::from_secs),
+            dry_run: self.dry_run,
+            quiet: self.quiet,
+        }
+    }
+}
+
+/// Parse the options and target names that follow the command
+fn parse_options(command: &TickleCommand, args: &[String]) -> Result<CliOptions, String> {
+    let mut options = CliOptions {
+        overrides: ServiceConfig::default(),
+        force: false,
+        dry_run: false,
+        quiet: false,
+        force_systemd: false,
+        compose: ComposeOptions {
+            wait: Some(DEFAULT_WAIT),
+            ..ComposeOptions::default()
+        },
+        logs: LogOptions::default(),
+        parallel: 1,
+        targets: Vec::new(),
+    };
+    let start_index = match command {
+        // Skip "tickle" and the command name
+        TickleCommand::Start
+        | TickleCommand::Stop
+        | TickleCommand::Inspect
+        | TickleCommand::Status
+        | TickleCommand::Logs => 2,
+        TickleCommand::Tickle => 1, // Skip just "tickle"
+        TickleCommand::History | TickleCommand::Stack => unreachable!(), // Handled separately
+    };
+
+    // Fetch the value that follows an option such as `--timeout 30`
+    fn value_of<'a>(args: &'a [String], i: &mut usize, option: &str) -> Result<&'a str, String> {
+        *i += 1;
+        args.get(*i)
+            .map(|v| v.as_str())
+            .ok_or_else(|| format!("{} requires a value", option))
+    }
+
+    let logs_only = |option: &str| {
+        if matches!(command, TickleCommand::Logs) {
+            Ok(())
+        } else {
+            Err(format!("{} option only valid with logs command", option))
+        }
+    };
+
+    let mut i = start_index;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-f" | "--follow" => {
+                options.overrides.follow = Some(true);
+            }
+            "--no-follow" => {
+                options.overrides.follow = Some(false);
+            }
+            "--show-logs" => {
+                options.overrides.show_logs = Some(true);
+            }
+            "--until-log" | "--fail-log" => {
+                let option = args[i].clone();
+                if matches!(command, TickleCommand::Logs) {
+                    return Err(format!("{} option not valid with logs command", option));
+                }
+                let pattern = value_of(args, &mut i, &option)?;
+                Regex::new(pattern).map_err(|e| format!("Invalid {} pattern: {}", option, e))?;
+                if option == "--until-log" {
+                    // Waiting for a line means following the logs
+                    options.overrides.ready_log = Some(pattern.to_string());
+                    options.overrides.follow = Some(true);
+                } else {
+                    // Watching for a failure means showing the new lines
+                    options.overrides.fail_log = Some(pattern.to_string());
+                    options.overrides.show_logs = Some(true);
+                }
+            }
+            "-s" | "--stop-start" => {
+                if matches!(command, TickleCommand::Tickle) {
+                    options.overrides.strategy = Some(RestartStrategy::StopStart);
+                } else {
+                    return Err("--stop-start option only valid with tickle command".to_string());
+                }
+            }
+            "--strategy" => {
+                if !matches!(command, TickleCommand::Tickle) {
+                    return Err("--strategy option only valid with tickle command".to_string());
+                }
+                options.overrides.strategy = Some(match value_of(args, &mut i, "--strategy")? {
+                    "restart" => RestartStrategy::Restart,
+                    "stop-start" => RestartStrategy::StopStart,
+                    other => {
+                        return Err(format!(
+                            "Unknown strategy '{}' (expected restart or stop-start)",
+                            other
+                        ));
+                    }
+                });
+            }
+            "--timeout" => {
+                options.overrides.timeout = Some(
+                    value_of(args, &mut i, "--timeout")?
+                        .parse::<u64>()
+                        .map_err(|_| "--timeout requires a number of seconds".to_string())?,
+                );
+            }
+            "--ready-timeout" => {
+                options.overrides.ready_timeout = Some(
+                    value_of(args, &mut i, "--ready-timeout")?
+                        .parse::<u64>()
+                        .map_err(|_| "--ready-timeout requires a number of seconds".to_string())?,
+                );
+            }
+            "--retries" => {
+                options.overrides.retries = Some(
+                    value_of(args, &mut i, "--retries")?
+                        .parse::<u32>()
+                        .map_err(|_| "--retries requires a number".to_string())?,
+                );
+            }
+            "--health-check" => {
+                options.overrides.health_check =
+                    Some(value_of(args, &mut i, "--health-check")?.to_string());
+            }
+            "--force" => {
+                options.force = true;
+            }
+            "--dry-run" => {
+                options.dry_run = true;
+            }
+            "-q" | "--quiet" => {
+                options.quiet = true;
+            }
+            "--systemd" => {
+                options.force_systemd = true;
+            }
+            "--file" => {
+                let file = value_of(args, &mut i, "--file")?;
+                options.compose.files.push(file.to_string());
+            }
+            "--profile" => {
+                let profile = value_of(args, &mut i, "--profile")?;
+                options.compose.profiles.push(profile.to_string());
+            }
+            "--env-file" => {
+                options.compose.env_file = Some(value_of(args, &mut i, "--env-file")?.to_string());
+            }
+            "--context" => {
+                options.compose.context = Some(value_of(args, &mut i, "--context")?.to_string());
+            }
+            "--pull" => {
+                if !matches!(command, TickleCommand::Tickle) {
+                    return Err("--pull option only valid with tickle command".to_string());
+                }
+                options.compose.pull = true;
+            }
+            "--wait-timeout" => {
+                let secs = value_of(args, &mut i, "--wait-timeout")?
+                    .parse::<u64>()
+                    .map_err(|_| "--wait-timeout requires a number of seconds".to_string())?;
+                options.compose.wait = Some(Duration::from_secs(secs));
+            }
+            "--no-wait" => {
+                options.compose.wait = None;
+            }
+            "--compose-strategy" => {
+                if !matches!(command, TickleCommand::Tickle) {
+                    return Err(
+                        "--compose-strategy option only valid with tickle command".to_string()
+                    );
+                }
+                let name = value_of(args, &mut i, "--compose-strategy")?;
+                options.compose.strategy = Some(ComposeStrategy::parse(name)?);
+            }
+            "-p" | "--project-name" => {
+                options.compose.project_name =
+                    Some(value_of(args, &mut i, "--project-name")?.to_string());
+            }
+            "--since" => {
+                logs_only("--since")?;
+                options.logs.since = Some(LogTime::parse(value_of(args, &mut i, "--since")?));
+            }
+            "--until" => {
+                logs_only("--until")?;
+                options.logs.until = Some(LogTime::parse(value_of(args, &mut i, "--until")?));
+            }
+            "--lines" => {
+                logs_only("--lines")?;
+                options.logs.lines = Some(
+                    value_of(args, &mut i, "--lines")?
+                        .parse::<usize>()
+                        .map_err(|_| "--lines requires a number".to_string())?,
+                );
+            }
+            "--grep" => {
+                logs_only("--grep")?;
+                options.logs.grep = Some(value_of(args, &mut i, "--grep")?.to_string());
+            }
+            "--priority" => {
+                logs_only("--priority")?;
+                options.logs.priority = Some(value_of(args, &mut i, "--priority")?.to_string());
+            }
+            "--parallel" => {
+                options.parallel = value_of(args, &mut i, "--parallel")?
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| "--parallel requires a positive number".to_string())?;
+            }
+            arg if !arg.starts_with('-') => {
+                options.targets.push(arg.to_string());
+            }
+            arg => {
+                return Err(format!("Unknown option: {}", arg));
+            }
+        }
+        i += 1;
+    }
+    // The logs command follows unless told not to
+    options.logs.follow = options.overrides.follow != Some(false);
+
+    Ok(options)
+}
+
+/// A named target to operate on, and the `@group` it was named through
+#[derive(Clone)]
+struct ServiceTarget {
+    name: String,
+    group: Option<String>,
+    /// Set when the name matched a service of the nearest compose file
+    compose_file: Option<PathBuf>,
+}
+
+impl ServiceTarget {
+    /// Target as recorded in history, e.g. `@web:nginx` for a group member
+    /// or `compose:/srv/app/compose.yml:web` for a compose service
+    fn history_label(&self) -> String {
+        let name = match &self.compose_file {
+            Some(file) => format!("compose:{}:{}", file.display(), self.name),
+            None => self.name.clone(),
+        };
+        match &self.group {
+            Some(group) => format!("@{}:{}", group, name),
+            None => name,
+        }
+    }
+
+    /// Effective settings: the command line over the config. A `stack:`
+    /// target is configured like any compose stack, by its project name.
+    fn settings(&self, options: &CliOptions, config: &Config) -> ServiceConfig {
+        let stack_file = self
+            .name
+            .strip_prefix("stack:")
+            .filter(|_| config.custom_type("stack").is_none())
+            .and_then(|name| {
+                StackRegistry::load()
+                    .ok()?
+                    .get(name)
+                    .ok()
+                    .map(Path::to_path_buf)
+            });
+        match stack_file {
+            Some(file) => stack_settings(options, config, &file),
+            None => options
+                .overrides
+                .clone()
+                .or(&config.for_service(&self.name)),
+        }
+    }
+
+    fn build<'a>(
+        &self,
+        config: &Config,
+        compose: &ComposeOptions,
+        out: &'a Output,
+        exec: ExecOptions,
+    ) -> Result<Box<dyn Target + 'a>, String> {
+        match &self.compose_file {
+            Some(file) => Ok(Box::new(
+                ComposeStack::new(file, vec![self.name.clone()], out, exec).with_options(compose),
+            )),
+            None => target::make_target(&self.name, config, compose, out, exec),
+        }
+    }
+}
+
+impl fmt::Display for ServiceTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.group {
+            Some(group) => write!(f, "{} (@{})", self.name, group)?,
+            None => write!(f, "{}", self.name)?,
+        }
+        match &self.compose_file {
+            Some(_) => write!(f, " [compose]"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Expand `@group` names from the config into their member services, and
+/// mark names that are services of the nearest compose file or standalone
+/// docker containers. With `--systemd`, names are always systemd units.
+fn resolve_targets(
+    command: &TickleCommand,
+    options: &CliOptions,
+    config: &Config,
+) -> Result<Vec<ServiceTarget>, String> {
+    let mut targets = Vec::new();
+    for name in &options.targets {
+        match name.strip_prefix('@') {
+            Some(group) => {
+                for member in config.expand_group(group)? {
+                    targets.push(ServiceTarget {
+                        name: member,
+                        group: Some(group.to_string()),
+                        compose_file: None,
+                    });
+                }
+            }
+            None => targets.push(ServiceTarget {
+                name: name.clone(),
+                group: None,
+                compose_file: None,
+            }),
+        }
+    }
+
+    // Typed `<type>:<name>` targets are never compose services
+    let plain = |t: &ServiceTarget| !target::is_typed(&t.name, config);
+    // Unit, container and compose service names can't hold a '/', so this
+    // is a path, most likely a project directory meant for -C
+    if let Some(path) = targets.iter().find(|t| plain(t) && t.name.contains('/')) {
+        return Err(format!(
+            "'{}' looks like a path, not a service name; to handle several projects use -C <dir>...",
+            path.name
+        ));
+    }
+    if options.force_systemd || !targets.iter().any(plain) {
+        return Ok(targets);
+    }
+
+    if let Some(compose_file) = compose::find_compose_file() {
+        let stack = ComposeStack::new(
+            &compose_file,
+            Vec::new(),
+            &Output::Stdout,
+            ExecOptions::default(),
+        )
+        .with_options(&options.compose);
+        match stack.defined_services() {
+            Ok(services) => {
+                for target in targets.iter_mut().filter(|t| plain(t)) {
+                    if services.contains(&target.name) {
+                        target.compose_file = Some(compose_file.clone());
+                    }
+                }
+                order_compose_services(&stack, command, &mut targets);
+            }
+            Err(e) => eprintln!(
+                "⚠️  Warning: Could not list services in {} ({}); treating names as systemd units",
+                compose_file.display(),
+                e
+            ),
+        }
+    }
+
+    // A name with no systemd unit behind it may be a standalone container
+    let manager = ServiceManager::new(&Output::Stdout);
+    for target in targets
+        .iter_mut()
+        .filter(|t| t.compose_file.is_none() && plain(t))
+    {
+        if !manager.unit_exists(&target.name)
+            && container::exists("docker", &target.name, options.compose.context.as_deref())
+        {
+            println!(
+                "🐳 No systemd unit named {}; using the docker container instead",
+                target.name
+            );
+            target.name = format!("container:{}", target.name);
+        }
+    }
+    Ok(targets)
+}
+
+/// Put the compose services among `targets` in `depends_on` order (reversed
+/// when stopping), keeping the slots they occupy among the other targets
+fn order_compose_services(
+    stack: &ComposeStack,
+    command: &TickleCommand,
+    targets: &mut [ServiceTarget],
+) {
+    let slots: Vec<usize> = (0..targets.len())
+        .filter(|&i| targets[i].compose_file.is_some())
+        .collect();
+    if slots.len() < 2 {
+        return;
+    }
+    let names: Vec<String> = slots.iter().map(|&i| targets[i].name.clone()).collect();
+    // An invalid compose file is reported when the services are acted on
+    let Ok(mut order) = stack
+        .project()
+        .and_then(|project| project.dependency_order(&names))
+    else {
+        return;
+    };
+    if matches!(command, TickleCommand::Stop) {
+        order.reverse();
+    }
+
+    let mut services: Vec<ServiceTarget> = slots.iter().map(|&i| targets[i].clone()).collect();
+    services.sort_by_key(|t| order.iter().position(|name| *name == t.name));
+    for (slot, service) in slots.into_iter().zip(services) {
+        targets[slot] = service;
+    }
+}
+
+/// `tickle stack add <name> <dir>`, `tickle stack list` and
+/// `tickle stack remove <name>`
+fn stack_command(args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let mut registry = StackRegistry::load()?;
+    match args.as_slice() {
+        ["add", name, location] => {
+            let file = registry.add(name, Path::new(location))?;
+            println!("✅ Registered stack:{} ({})", name, file.display());
+        }
+        ["list"] | [] => registry.list(),
+        ["remove", name] => {
+            registry.remove(name)?;
+            println!("✅ Removed stack:{}", name);
+        }
+        _ => {
+            return Err(
+                "usage: tickle stack add <name> <dir|file> | stack list | stack remove <name>"
+                    .to_string(),
+            );
+        }
+    }
+    Ok(())
+}
+
 /// Parse command from arguments
 fn parse_command(args: &[String]) -> TickleCommand {
     if args.len() > 1 {
@@ -539,6 +1137,10 @@ fn parse_command(args: &[String]) -> TickleCommand {
             "start" => TickleCommand::Start,
             "stop" => TickleCommand::Stop,
             "history" => TickleCommand::History,
+            "stack" => TickleCommand::Stack,
+            "inspect" => TickleCommand::Inspect,
+            "status" => TickleCommand::Status,
+            "logs" => TickleCommand::Logs,
             _ => TickleCommand::Tickle,
         }
     } else {
@@ -546,8 +1148,301 @@ fn parse_command(args: &[String]) -> TickleCommand {
     }
 }
 
+/// Handle `-C <dir>` like git does: remove it from the arguments and run
+/// as if tickle had been started in that directory. Repeating `-C`, or
+/// following it with more directory paths (`-C ~/stacks/*`), names several
+/// compose projects; those are returned instead of changing directory. Only
+/// arguments containing a '/' are taken as extra directories, so
+/// `-C /srv/app nginx` still means the nginx service.
+fn change_directory(args: Vec<String>) -> Result<(Vec<String>, Vec<PathBuf>), String> {
+    let mut remaining = Vec::with_capacity(args.len());
+    let mut directories = Vec::new();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        if arg != "-C" {
+            remaining.push(arg);
+            continue;
+        }
+        directories.push(args.next().ok_or("-C requires a directory")?);
+        while let Some(dir) = args
+            .next_if(|arg| arg.contains('/') && !arg.starts_with('-') && Path::new(arg).is_dir())
+        {
+            directories.push(dir);
+        }
+    }
+
+    let mut directories = directories
+        .iter()
+        .map(|dir| {
+            PathBuf::from(dir)
+                .canonicalize()
+                .map_err(|e| format!("Cannot change to directory {}: {}", dir, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    directories.dedup();
+    if let [dir] = directories.as_slice() {
+        env::set_current_dir(dir)
+            .map_err(|e| format!("Cannot change to directory {}: {}", dir.display(), e))?;
+        directories.clear();
+    }
+    Ok((remaining, directories))
+}
+
+/// `tickle inspect [service...]`: show what the nearest compose file
+/// defines, as tickle reads it
+fn inspect_compose(options: &CliOptions) -> Result<(), String> {
+    let compose_file = compose::find_compose_file()
+        .ok_or("No compose file found in this directory or its parents")?;
+    let stack = ComposeStack::new(
+        &compose_file,
+        Vec::new(),
+        &Output::Stdout,
+        ExecOptions::default(),
+    )
+    .with_options(&options.compose);
+    let project = stack.project()?;
+
+    println!("📄 Compose project {}\n", stack.path());
+    project.print(&options.targets)?;
+    if !project.unset_variables.is_empty() {
+        println!();
+    }
+    for name in &project.unset_variables {
+        println!("⚠️  Warning: {} is not set; using an empty string", name);
+    }
+    Ok(())
+}
+
+/// `tickle status [name...]`: a read-only view of the named targets, or of
+/// the compose project here (or in each `-C` directory). Compose services
+/// share one table.
+fn show_status(options: &CliOptions, config: &Config, dirs: &[PathBuf]) -> Result<(), String> {
+    let exec = options.exec(&options.overrides);
+    let mut sections: Vec<Box<dyn Target>> = Vec::new();
+    if options.targets.is_empty() {
+        let files = if dirs.is_empty() {
+            vec![
+                compose::find_compose_file()
+                    .ok_or("No service name provided and no compose file found")?,
+            ]
+        } else {
+            dirs.iter()
+                .map(|dir| {
+                    compose::compose_file_in(dir)
+                        .ok_or_else(|| format!("No compose file found in {}", dir.display()))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        for file in files {
+            sections.push(Box::new(
+                ComposeStack::new(&file, Vec::new(), &Output::Stdout, exec)
+                    .with_options(&options.compose),
+            ));
+        }
+    } else {
+        if !dirs.is_empty() {
+            return Err("Service names can't be combined with several -C directories".to_string());
+        }
+        let (services, others): (Vec<_>, Vec<_>) =
+            resolve_targets(&TickleCommand::Status, options, config)?
+                .into_iter()
+                .partition(|t| t.compose_file.is_some());
+        if let Some(file) = services.first().and_then(|t| t.compose_file.as_ref()) {
+            let names = services.iter().map(|t| t.name.clone()).collect();
+            sections.push(Box::new(
+                ComposeStack::new(file, names, &Output::Stdout, exec)
+                    .with_options(&options.compose),
+            ));
+        }
+        for target in &others {
+            sections.push(target.build(config, &options.compose, &Output::Stdout, exec)?);
+        }
+    }
+
+    let mut failed = 0;
+    for (i, target) in sections.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        if let Err(e) = target.print_status() {
+            eprintln!("❌ {}: {}", target.name(), e);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(format!("Could not get the status of {} target(s)", failed));
+    }
+    Ok(())
+}
+
+/// The first log-watching flag given on the command line, for refusing it
+/// where there isn't a single target to watch
+fn log_watch_flag(overrides: &ServiceConfig) -> Option<&'static str> {
+    [
+        ("--until-log", overrides.ready_log.is_some()),
+        ("--fail-log", overrides.fail_log.is_some()),
+        ("--follow", overrides.follow == Some(true)),
+        ("--show-logs", overrides.show_logs == Some(true)),
+    ]
+    .into_iter()
+    .find_map(|(flag, set)| set.then_some(flag))
+}
+
+/// Whether to show what a target logs from just before the operation on:
+/// with `--show-logs`, or when following logs to watch for a pattern
+fn watches_logs(settings: &ServiceConfig) -> bool {
+    settings.show_logs == Some(true)
+        || (settings.follow == Some(true)
+            && (settings.ready_log.is_some() || settings.fail_log.is_some()))
+}
+
+/// Print what a target logged since `since` was taken. After a success this
+/// follows until the `ready_log` pattern matches (or, with `-f` and no
+/// pattern, until Ctrl+C); after a failure it prints what was logged. The
+/// outcome of waiting for a pattern is recorded in history. Exits on error.
+fn show_new_logs(
+    target: &dyn Target,
+    mut since: LogOptions,
+    settings: &ServiceConfig,
+    success: bool,
+    dry_run: bool,
+    history_label: &str,
+    history_manager: &HistoryManager,
+) {
+    let watch = LogWatch {
+        ready: settings.ready_log.as_deref().filter(|_| success),
+        fail: settings.fail_log.as_deref(),
+        timeout: settings
+            .ready_timeout
+            .map_or(logs::DEFAULT_READY_WAIT, Duration::from_secs),
+    };
+    since.follow = success && settings.follow == Some(true);
+    let result = logs::show_new(target, since, &watch, dry_run);
+
+    if (watch.ready.is_some() || watch.fail.is_some()) && !dry_run {
+        let note = match &result {
+            Ok(Some(line)) => Some(format!("matched: {}", line)),
+            Ok(None) => None,
+            Err(e) => Some(HistoryManager::failure_note(e)),
+        };
+        if let Err(e) =
+            history_manager.log_command("until-log", history_label, result.is_ok(), note.as_deref())
+        {
+            eprintln!("⚠️  Warning: Failed to log to history: {}", e);
+        }
+    }
+    if let Err(e) = result {
+        eprintln!("❌ Error: {}", e);
+        exit(1);
+    }
+}
+
+/// `tickle logs [name]`: the logs of one target, or of the compose stack
+/// here
+fn show_logs(options: &CliOptions, config: &Config, dirs: &[PathBuf]) -> Result<(), String> {
+    if !dirs.is_empty() {
+        return Err("logs can only be shown for one -C directory at a time".to_string());
+    }
+    let exec = options.exec(&options.overrides);
+    let target = match options.targets.as_slice() {
+        [] => {
+            let file = compose::find_compose_file()
+                .ok_or("No service name provided and no compose file found")?;
+            Box::new(
+                ComposeStack::new(&file, Vec::new(), &Output::Stdout, exec)
+                    .with_options(&options.compose),
+            )
+        }
+        [_] => match resolve_targets(&TickleCommand::Logs, options, config)?.as_slice() {
+            [target] => target.build(config, &options.compose, &Output::Stdout, exec)?,
+            _ => return Err("logs takes a single service, not a group".to_string()),
+        },
+        _ => return Err("logs takes a single service".to_string()),
+    };
+    logs::show(target.as_ref(), &options.logs, options.dry_run)
+}
+
+/// Settings for a whole compose stack: the command line over
+/// `[services.<project>]` over `[defaults]`, where the project is named as
+/// compose names it (`-p`, `COMPOSE_PROJECT_NAME`, or the directory name)
+fn stack_settings(options: &CliOptions, config: &Config, compose_file: &Path) -> ServiceConfig {
+    let project = options
+        .compose
+        .project_name
+        .clone()
+        .or_else(|| env::var("COMPOSE_PROJECT_NAME").ok())
+        .or_else(|| {
+            compose_file
+                .parent()
+                .and_then(Path::file_name)
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    options.overrides.clone().or(&config.for_service(&project))
+}
+
+/// Tickle, start or stop the compose project in each of `dirs`, printing a
+/// summary and logging each project to history separately
+fn run_projects(
+    dirs: &[PathBuf],
+    command: &TickleCommand,
+    options: &CliOptions,
+    config: &Config,
+    history_manager: &HistoryManager,
+) -> ! {
+    if !options.targets.is_empty() {
+        eprintln!("❌ Error: Service names can't be combined with several -C directories");
+        exit(1);
+    }
+    if let Some(flag) = log_watch_flag(&options.overrides) {
+        eprintln!("❌ Error: {} can only be used with a single project", flag);
+        exit(1);
+    }
+
+    let labels: Vec<String> = dirs.iter().map(|dir| dir.display().to_string()).collect();
+    let results = runner::run_jobs(&labels, options.parallel, |dir, out| {
+        let dir = Path::new(dir);
+        let Some(compose_file) = compose::compose_file_in(dir) else {
+            return Err(format!("No compose file found in {}", dir.display()));
+        };
+        let settings = stack_settings(options, config, &compose_file);
+        let exec = options.exec(&settings);
+        let stack =
+            ComposeStack::new(&compose_file, Vec::new(), out, exec).with_options(&options.compose);
+        let result =
+            target::run_target_command(command, &stack, &settings, options.force, exec, out);
+
+        if !options.dry_run
+            && let Err(e) = history_manager.log_command(
+                command.name(),
+                &format!("compose:{}", compose_file.display()),
+                result.is_ok(),
+                HistoryManager::note_for(&result, &stack).as_deref(),
+            )
+        {
+            eprintln!("⚠️  Warning: Failed to log to history: {}", e);
+        }
+        result
+    });
+    runner::print_summary(&results);
+
+    if results.iter().any(|r| r.result.is_err()) {
+        exit(1);
+    }
+    if options.dry_run {
+        println!("🔍 Dry run: no changes were made");
+    }
+    exit(0);
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let (args, directories) = match change_directory(env::args().collect()) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("❌ Error: {}", e);
+            exit(1);
+        }
+    };
 
     let command = parse_command(&args);
 
@@ -617,101 +1512,181 @@ fn main() {
         }
     }
 
-    // Determine if we have a service name and parse other options
-    let mut force_stop_start = false;
-    let mut follow = false;
-    let mut service_name = "";
-    let start_index = match command {
-        TickleCommand::Start | TickleCommand::Stop => 2, // Skip "tickle" and "start"/"stop"
-        TickleCommand::Tickle => 1,                      // Skip just "tickle"
-        TickleCommand::History => unreachable!(),        // Already handled above
+    // Handle stack registry commands
+    if matches!(command, TickleCommand::Stack) {
+        match stack_command(&args[2..]) {
+            Ok(()) => exit(0),
+            Err(e) => {
+                eprintln!("❌ Error: {}", e);
+                exit(1);
+            }
+        }
+    }
+
+    // Parse options and service names
+    let options = match parse_options(&command, &args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("❌ Error: {}", e);
+            print_usage();
+            exit(1);
+        }
     };
 
-    // Parse remaining arguments
-    let mut i = start_index;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-f" | "--follow" => {
-                follow = true;
-            }
-            "-s" | "--stop-start" => {
-                if matches!(command, TickleCommand::Tickle) {
-                    force_stop_start = true;
-                } else {
-                    eprintln!("❌ Error: --stop-start option only valid with tickle command");
-                    exit(1);
-                }
-            }
-            arg if !arg.starts_with('-') => {
-                service_name = arg;
-                break;
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ Config error: {}", e);
+            exit(1);
+        }
+    };
+
+    if matches!(command, TickleCommand::Inspect) {
+        match inspect_compose(&options) {
+            Ok(()) => exit(0),
+            Err(e) => {
+                eprintln!("❌ Error: {}", e);
+                exit(1);
             }
-            _ => {
-                eprintln!("❌ Error: Unknown option: {}", args[i]);
-                print_usage();
+        }
+    }
+
+    if matches!(command, TickleCommand::Status) {
+        match show_status(&options, &config, &directories) {
+            Ok(()) => exit(0),
+            Err(e) => {
+                eprintln!("❌ Error: {}", e);
                 exit(1);
             }
         }
-        i += 1;
     }
 
-    // Determine the target for history logging
-    let target: String;
+    if matches!(command, TickleCommand::Logs) {
+        match show_logs(&options, &config, &directories) {
+            Ok(()) => exit(0),
+            Err(e) => {
+                eprintln!("❌ Error: {}", e);
+                exit(1);
+            }
+        }
+    }
 
-    // Handle compose file operations when no service name is provided
-    if service_name.is_empty() {
-        if let Some(compose_file) = find_compose_file() {
-            // Get current directory name for better history context
-            let dir_name = env::current_dir()
-                .ok()
-                .and_then(|path| {
-                    path.file_name()
-                        .map(|name| name.to_string_lossy().to_string())
-                })
-                .unwrap_or_else(|| "unknown".to_string());
+    if !directories.is_empty() {
+        run_projects(&directories, &command, &options, &config, &history_manager);
+    }
 
-            target = format!("compose:{}:{}", dir_name, compose_file);
+    // Without a service name, a project file takes precedence over a compose file
+    if options.targets.is_empty() {
+        let project = match Project::find(Path::new(".")) {
+            Ok(project) => project,
+            Err(e) => {
+                eprintln!("❌ Project file error: {}", e);
+                exit(1);
+            }
+        };
+        if let Some(project) = project {
+            if let Some(flag) = log_watch_flag(&options.overrides) {
+                eprintln!(
+                    "❌ Error: {} is not supported for {} projects",
+                    flag,
+                    project::PROJECT_FILE
+                );
+                exit(1);
+            }
+            match project.run(&command, &options, &config, &history_manager) {
+                Ok(()) => {
+                    println!("\n🎉 Project {} completed successfully!", command.name());
+                    exit(0);
+                }
+                Err(e) => {
+                    eprintln!("❌ Error: {}", e);
+                    exit(1);
+                }
+            }
+        }
+    }
 
-            let result = match command {
-                TickleCommand::Tickle => compose_down_up(compose_file),
-                TickleCommand::Start => compose_start(compose_file),
-                TickleCommand::Stop => compose_stop(compose_file),
-                TickleCommand::History => unreachable!(),
+    // Handle compose file operations when no service name is provided
+    if options.targets.is_empty() {
+        if let Some(compose_file) = compose::find_compose_file() {
+            // Record the absolute compose file path for better history context
+            let target = format!("compose:{}", compose_file.display());
+            let settings = stack_settings(&options, &config, &compose_file);
+            let exec = options.exec(&settings);
+            let stack = ComposeStack::new(&compose_file, Vec::new(), &Output::Stdout, exec)
+                .with_options(&options.compose);
+            let new_logs = match watches_logs(&settings).then(|| stack.new_logs()) {
+                Some(Err(e)) => {
+                    eprintln!("❌ Error: {}", e);
+                    exit(1);
+                }
+                new_logs => new_logs.and_then(Result::ok),
             };
 
+            let result = target::run_target_command(
+                &command,
+                &stack,
+                &settings,
+                options.force,
+                exec,
+                &Output::Stdout,
+            );
+
             let success = result.is_ok();
-            let cmd_name = match command {
-                TickleCommand::Tickle => "tickle",
-                TickleCommand::Start => "start",
-                TickleCommand::Stop => "stop",
-                TickleCommand::History => unreachable!(),
-            };
+            let cmd_name = command.name();
 
             // Log to history
-            if let Err(e) = history_manager.log_command(cmd_name, &target, success) {
+            if !options.dry_run
+                && let Err(e) = history_manager.log_command(
+                    cmd_name,
+                    &target,
+                    success,
+                    HistoryManager::note_for(&result, &stack).as_deref(),
+                )
+            {
                 eprintln!("⚠️  Warning: Failed to log to history: {}", e);
             }
 
-            match result {
-                Ok(()) => {
-                    println!("🎉 Compose {} completed successfully!", cmd_name);
-                    if follow {
-                        follow_compose_logs(compose_file);
-                    }
-                    exit(0);
-                }
-                Err(e) => {
-                    eprintln!("❌ Compose error: {}", e);
-                    exit(1);
+            match &result {
+                Ok(()) => println!("🎉 Compose {} completed successfully!", cmd_name),
+                Err(e) => eprintln!("❌ Compose error: {}", e),
+            }
+            match new_logs {
+                Some(since) => show_new_logs(
+                    &stack,
+                    since,
+                    &settings,
+                    success,
+                    options.dry_run,
+                    &target,
+                    &history_manager,
+                ),
+                None if success && settings.follow == Some(true) => {
+                    logs::follow(&stack, options.dry_run)
                 }
+                None => {}
             }
+            exit(if success { 0 } else { 1 });
         } else {
             eprintln!("❌ Error: No service name provided and no compose file found");
             print_usage();
             exit(1);
         }
-    } else {
-        target = service_name.to_string();
+    }
+
+    let targets = match resolve_targets(&command, &options, &config) {
+        Ok(targets) => targets,
+        Err(e) => {
+            eprintln!("❌ Error: {}", e);
+            exit(1);
+        }
+    };
+
+    if let Some(flag) = log_watch_flag(&options.overrides)
+        && targets.len() > 1
+    {
+        eprintln!("❌ Error: {} can only be used with a single service", flag);
+        exit(1);
     }
 
     // Check if running as root/with sudo for systemd operations
@@ -723,63 +1698,134 @@ fn main() {
         }
     }
 
-    let service_manager = ServiceManager::new();
-
-    let result = match command {
-        TickleCommand::Tickle => service_manager.tickle_service(service_name, force_stop_start),
-        TickleCommand::Start => service_manager
-            .check_systemctl_available()
-            .and_then(|_| service_manager.start_service(service_name)),
-        TickleCommand::Stop => service_manager
-            .check_systemctl_available()
-            .and_then(|_| service_manager.stop_service(service_name)),
-        TickleCommand::History => unreachable!(),
+    // With --show-logs or --until-log, note where a single target's logs
+    // stand before it changes
+    let new_logs = match targets.as_slice() {
+        [service_target] => {
+            let settings = service_target.settings(&options, &config);
+            let exec = options.exec(&settings);
+            if watches_logs(&settings) {
+                match service_target.build(&config, &options.compose, &Output::Stdout, exec) {
+                    Ok(target) => match target.new_logs() {
+                        Ok(since) => Some((since, target, settings)),
+                        Err(e) => {
+                            eprintln!("❌ Error: {}", e);
+                            exit(1);
+                        }
+                    },
+                    // Reported when the operation runs
+                    Err(_) => None,
+                }
+            } else {
+                None
+            }
+        }
+        _ => None,
     };
 
-    let success = result.is_ok();
-    let cmd_name = match command {
-        TickleCommand::Tickle => "tickle",
-        TickleCommand::Start => "start",
-        TickleCommand::Stop => "stop",
-        TickleCommand::History => unreachable!(),
-    };
+    let results = runner::run_jobs(&targets, options.parallel, |service_target, out| {
+        let settings = service_target.settings(&options, &config);
+        let exec = options.exec(&settings);
+        let target = service_target.build(&config, &options.compose, out, exec);
+        let result = target.as_ref().map_err(Clone::clone).and_then(|target| {
+            target::run_target_command(
+                &command,
+                target.as_ref(),
+                &settings,
+                options.force,
+                exec,
+                out,
+            )
+        });
+        let note = match &target {
+            Ok(target) => HistoryManager::note_for(&result, target.as_ref()),
+            Err(e) => Some(HistoryManager::failure_note(e)),
+        };
 
-    // Log to history
-    if let Err(e) = history_manager.log_command(cmd_name, &target, success) {
-        eprintln!("⚠️  Warning: Failed to log to history: {}", e);
-    }
+        // Log to history
+        if !options.dry_run
+            && let Err(e) = history_manager.log_command(
+                command.name(),
+                &service_target.history_label(),
+                result.is_ok(),
+                note.as_deref(),
+            )
+        {
+            eprintln!("⚠️  Warning: Failed to log to history: {}", e);
+        }
 
-    match result {
-        Ok(()) => {
-            println!(
+        if result.is_ok() {
+            out.line(format!(
                 "🎉 {} completed successfully!",
                 match command {
                     TickleCommand::Tickle => "Tickle",
                     TickleCommand::Start => "Start",
                     TickleCommand::Stop => "Stop",
-                    TickleCommand::History => unreachable!(),
+                    TickleCommand::History
+                    | TickleCommand::Stack
+                    | TickleCommand::Inspect
+                    | TickleCommand::Status
+                    | TickleCommand::Logs => unreachable!(),
                 }
-            );
+            ));
 
             // Verify final state for non-tickle operations
-            if !matches!(command, TickleCommand::Tickle) {
-                match service_manager.get_service_state(service_name) {
+            if !matches!(command, TickleCommand::Tickle)
+                && let Ok(target) = &target
+            {
+                match target.state() {
                     Ok(final_state) => {
-                        println!("📊 Final state: {:?}", final_state);
+                        out.line(format!("📊 Final state: {:?}", final_state));
                     }
                     Err(e) => {
-                        println!("⚠️  Warning: Could not verify final state: {}", e);
+                        out.line(format!("⚠️  Warning: Could not verify final state: {}", e));
                     }
                 }
             }
+        }
+
+        result
+    });
+
+    if results.len() > 1 {
+        runner::print_summary(&results);
+    }
+
+    let success = results.iter().all(|r| r.result.is_ok());
+    let showed_logs = new_logs.is_some();
+    if let (Some((since, target, settings)), [service_target]) = (new_logs, targets.as_slice()) {
+        show_new_logs(
+            target.as_ref(),
+            since,
+            &settings,
+            success,
+            options.dry_run,
+            &service_target.history_label(),
+            &history_manager,
+        );
+    }
+    if !success {
+        exit(1);
+    }
+
+    if options.dry_run {
+        println!("🔍 Dry run: no changes were made");
+    }
 
-            if follow {
-                follow_service_logs(service_name);
+    // Follow logs when asked to, either on the command line or in the config
+    if let [service_target] = targets.as_slice()
+        && !showed_logs
+    {
+        let settings = service_target.settings(&options, &config);
+        if settings.follow == Some(true) {
+            let exec = options.exec(&settings);
+            match service_target.build(&config, &options.compose, &Output::Stdout, exec) {
+                Ok(target) => logs::follow(target.as_ref(), options.dry_run),
+                Err(e) => {
+                    eprintln!("❌ Error: {}", e);
+                    exit(1);
+                }
             }
         }
-        Err(e) => {
-            eprintln!("❌ Error: {}", e);
-            exit(1);
-        }
     }
 }
@@ -0,0 +1,60 @@
+// src/output.rs
+use std::sync::Mutex;
+
+/// Destination for the progress messages of a single target.
+pub enum Output {
+    /// Print each line to stdout as soon as it is produced
+    Stdout,
+    /// Collect lines so a finished target can be printed as one block
+    Buffered(Mutex<Vec<String>>),
+}
+
+impl Output {
+    pub fn buffered() -> Self {
+        Output::Buffered(Mutex::new(Vec::new()))
+    }
+
+    /// Emit one line of progress output
+    pub fn line(&self, message: impl Into<String>) {
+        match self {
+            Output::Stdout => println!("{}", message.into()),
+            Output::Buffered(lines) => lines
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(message.into()),
+        }
+    }
+
+    /// Consume the output and return any buffered lines
+    pub fn into_lines(self) -> Vec<String> {
+        match self {
+            Output::Stdout => Vec::new(),
+            Output::Buffered(lines) => lines.into_inner().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+}
+
+/// Print rows as a table whose first row is the header, with each column as
+/// wide as its widest cell
+pub fn print_table<const N: usize>(rows: &[[String; N]]) {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let mut line = String::new();
+        for (column, (cell, width)) in row.iter().zip(widths).enumerate() {
+            if column + 1 == N {
+                line.push_str(cell);
+            } else {
+                line.push_str(&format!("{:<width$}  ", cell, width = width));
+            }
+        }
+        println!("{}", line);
+        if i == 0 {
+            println!("{}", "-".repeat(widths.iter().sum::<usize>() + 2 * (N - 1)));
+        }
+    }
+}
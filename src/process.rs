@@ -0,0 +1,182 @@
+// src/process.rs
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::output;
+
+/// Lines of stdout and stderr kept from a command for error reporting
+const TAIL_LINES: usize = 20;
+
+/// How often a running command is checked for exit or timeout
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long to keep reading after a command exits, for output still in its
+/// pipes; a background process it left behind may hold them open for good
+const EXIT_DRAIN: Duration = Duration::from_millis(500);
+
+/// How state-changing commands should be executed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecOptions {
+    /// Kill a command that runs longer than this
+    pub timeout: Option<Duration>,
+    /// Print commands instead of running them
+    pub dry_run: bool,
+    /// Don't stream the command's output as it runs
+    pub quiet: bool,
+}
+
+/// Run a state-changing command. Its output is streamed line by line,
+/// prefixed with the program name, unless `exec.quiet` is set; the returned
+/// `Output` holds the last lines of each stream. In dry-run mode the command
+/// is only printed and reported as successful.
+pub fn run(command: &mut Command, exec: ExecOptions, out: &output::Output) -> io::Result<Output> {
+    if exec.dry_run {
+        out.line(format!("🔍 Would run: {}", describe(command)));
+        return Ok(Output {
+            status: ExitStatus::from_raw(0),
+            stdout: Vec::new(),
+            stderr: Vec::new(),
+        });
+    }
+
+    let program = Path::new(command.get_program())
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let prefix = format!("   {} │ ", program);
+    let echo = (!exec.quiet).then_some((out, prefix.as_str()));
+    stream_with_timeout(command, exec.timeout, echo)
+}
+
+/// Render a command as a shell-like string for display
+pub fn describe(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| {
+            let arg = arg.to_string_lossy();
+            if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || "'\"$`\\".contains(c))
+            {
+                shell_quote(&arg)
+            } else {
+                arg.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote a string for safe use as a single `sh` word
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Which pipe a line of output came from
+#[derive(Clone, Copy)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Run a command to completion, echoing each output line to `echo` (with
+/// its prefix) as it arrives, and kill it if it runs longer than `timeout`.
+/// Only the last `TAIL_LINES` lines of each stream are returned.
+fn stream_with_timeout(
+    command: &mut Command,
+    timeout: Option<Duration>,
+    echo: Option<(&output::Output, &str)>,
+) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Read both pipes on their own threads so a chatty child can't block.
+    // They aren't joined, so a killed child's orphans can't hold us up.
+    let (tx, rx) = mpsc::channel();
+    if let Some(pipe) = child.stdout.take() {
+        forward_lines(pipe, Stream::Stdout, tx.clone());
+    }
+    if let Some(pipe) = child.stderr.take() {
+        forward_lines(pipe, Stream::Stderr, tx);
+    }
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    let mut stdout = VecDeque::new();
+    let mut stderr = VecDeque::new();
+    let mut pipes_open = true;
+    let mut status = None;
+    let status = loop {
+        if pipes_open {
+            match rx.recv_timeout(POLL_INTERVAL) {
+                Ok((stream, line)) => {
+                    if let Some((out, prefix)) = echo {
+                        out.line(format!("{}{}", prefix, line));
+                    }
+                    let tail = match stream {
+                        Stream::Stdout => &mut stdout,
+                        Stream::Stderr => &mut stderr,
+                    };
+                    if tail.len() == TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => pipes_open = false,
+            }
+        } else {
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        if status.is_none() {
+            status = child.try_wait()?.map(|status| (status, Instant::now()));
+        }
+        match status {
+            Some((status, exited)) if !pipes_open || exited.elapsed() >= EXIT_DRAIN => {
+                break status;
+            }
+            None if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                child.kill().ok();
+                child.wait().ok();
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("timed out after {}s", timeout.unwrap_or_default().as_secs()),
+                ));
+            }
+            _ => {}
+        }
+    };
+
+    let join = |tail: VecDeque<String>| {
+        tail.into_iter()
+            .map(|line| line + "\n")
+            .collect::<String>()
+            .into_bytes()
+    };
+    Ok(Output {
+        status,
+        stdout: join(stdout),
+        stderr: join(stderr),
+    })
+}
+
+fn forward_lines(pipe: impl Read + Send + 'static, stream: Stream, tx: Sender<(Stream, String)>) {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        while reader.read_until(b'\n', &mut buf).is_ok_and(|n| n > 0) {
+            let line = String::from_utf8_lossy(&buf).trim_end().to_string();
+            buf.clear();
+            if tx.send((stream, line)).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,334 @@
+// src/project.rs
+//! Per-directory `.tickle.toml` project files.
+//!
+//! A project file lists the steps that make up "tickle this project", e.g. a
+//! compose stack plus a host-level worker unit. Steps run in file order for
+//! `tickle` and `start`, and in reverse order for `stop`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::compose::{self, ComposeStack, ComposeStrategy};
+use crate::config::Config;
+use crate::output::Output;
+use crate::process;
+use crate::target::{self, Target, run_health_check, run_target_command};
+use crate::toml::{self, Table, Value};
+use crate::{CliOptions, HistoryManager, TickleCommand, stack_settings};
+
+pub const PROJECT_FILE: &str = ".tickle.toml";
+
+pub struct Project {
+    path: PathBuf,
+    steps: Vec<Step>,
+}
+
+struct Step {
+    name: Option<String>,
+    kind: StepKind,
+    health_check: Option<String>,
+}
+
+enum StepKind {
+    /// A compose stack, or only some of its services
+    Compose {
+        file: Option<String>,
+        services: Vec<String>,
+        strategy: Option<ComposeStrategy>,
+    },
+    /// A systemd unit or custom target, handled like `tickle <unit>`
+    Unit(String),
+    /// A shell command run for the listed commands only
+    Command { run: String, on: Vec<TickleCommand> },
+}
+
+impl Step {
+    /// Short description used in output and history
+    fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        match &self.kind {
+            StepKind::Compose { services, .. } if services.is_empty() => "compose".to_string(),
+            StepKind::Compose { services, .. } => format!("compose:{}", services.join(",")),
+            StepKind::Unit(unit) => unit.clone(),
+            StepKind::Command { run, .. } => run.clone(),
+        }
+    }
+}
+
+impl Project {
+    /// Load the nearest `.tickle.toml`, looking in `start` and then its
+    /// parents like the compose file search. A directory with a compose
+    /// file but no project file ends the search, so the closer compose
+    /// project wins.
+    pub fn find(start: &Path) -> Result<Option<Project>, String> {
+        let start = start
+            .canonicalize()
+            .map_err(|e| format!("{}: {}", start.display(), e))?;
+        let found = compose::search_up(&start, |dir| {
+            let path = dir.join(PROJECT_FILE);
+            if path.exists() {
+                Some(Some(path))
+            } else {
+                compose::compose_file_in(dir).map(|_| None)
+            }
+        });
+        let Some(path) = found.flatten() else {
+            return Ok(None);
+        };
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let steps = parse_steps(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(Some(Project { path, steps }))
+    }
+
+    /// Run every step of the project, stopping at the first failure
+    pub fn run(
+        &self,
+        command: &TickleCommand,
+        options: &CliOptions,
+        config: &Config,
+        history: &HistoryManager,
+    ) -> Result<(), String> {
+        let project_dir = self
+            .path
+            .parent()
+            .and_then(|dir| dir.canonicalize().ok())
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let mut steps: Vec<&Step> = self.steps.iter().collect();
+        if matches!(command, TickleCommand::Stop) {
+            steps.reverse();
+        }
+        steps.retain(|step| match &step.kind {
+            StepKind::Command { on, .. } => on.contains(command),
+            _ => true,
+        });
+
+        println!(
+            "📁 Project file {} ({} step{})",
+            self.path.display(),
+            steps.len(),
+            if steps.len() == 1 { "" } else { "s" }
+        );
+
+        for (i, step) in steps.iter().enumerate() {
+            let label = step.label();
+            println!("\n── step {}/{}: {} ──", i + 1, steps.len(), label);
+
+            let result = self.run_step(step, command, options, config);
+            let target = format!("project:{}:{}", project_dir, label);
+            let note = match &result {
+                Ok(note) => note.clone(),
+                Err(e) => Some(HistoryManager::failure_note(e)),
+            };
+            if !options.dry_run
+                && let Err(e) =
+                    history.log_command(command.name(), &target, result.is_ok(), note.as_deref())
+            {
+                eprintln!("⚠️  Warning: Failed to log to history: {}", e);
+            }
+            result.map_err(|e| format!("Step '{}' failed: {}", label, e))?;
+        }
+        Ok(())
+    }
+
+    /// Run one step; on success, returns the note to record in history
+    fn run_step(
+        &self,
+        step: &Step,
+        command: &TickleCommand,
+        options: &CliOptions,
+        config: &Config,
+    ) -> Result<Option<String>, String> {
+        let out = Output::Stdout;
+        // CLI options win, then the step, then the target's config
+        let mut settings = options.overrides.clone();
+        settings.health_check = settings.health_check.or(step.health_check.clone());
+
+        match &step.kind {
+            StepKind::Unit(unit) => {
+                let settings = settings.or(&config.for_service(unit));
+                let exec = options.exec(&settings);
+                let target = target::make_target(unit, config, &options.compose, &out, exec)?;
+                run_target_command(
+                    command,
+                    target.as_ref(),
+                    &settings,
+                    options.force,
+                    exec,
+                    &out,
+                )?;
+                Ok(target.history_note())
+            }
+            StepKind::Compose {
+                file,
+                services,
+                strategy,
+            } => {
+                // Relative to the project file, wherever tickle runs from
+                let project_dir = self.path.parent().unwrap_or(Path::new("."));
+                let file = match file {
+                    Some(file) => project_dir.join(file),
+                    None => compose::find_compose_file_from(project_dir)
+                        .ok_or("no compose file found")?,
+                };
+                let settings = settings.or(&stack_settings(options, config, &file));
+                let exec = options.exec(&settings);
+                let mut compose = options.compose.clone();
+                compose.strategy = compose.strategy.or(*strategy);
+                let stack =
+                    ComposeStack::new(&file, services.clone(), &out, exec).with_options(&compose);
+                run_target_command(command, &stack, &settings, options.force, exec, &out)?;
+                Ok(stack.history_note())
+            }
+            StepKind::Command { run, .. } => {
+                let exec = options.exec(&settings);
+                if !exec.dry_run {
+                    out.line(format!("⚙️  Running: {}", run));
+                }
+                let project_dir = self.path.parent().unwrap_or(Path::new("."));
+                let mut cmd = Command::new("sh");
+                cmd.args(["-c", run]).current_dir(project_dir);
+                let output = process::run(&mut cmd, exec, &out)
+                    .map_err(|e| format!("Failed to run command: {}", e))?;
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    return Err(match stderr.lines().last() {
+                        Some(line) => format!("command exited with {}: {}", output.status, line),
+                        None => format!("command exited with {}", output.status),
+                    });
+                }
+                if let (Some(check), TickleCommand::Tickle | TickleCommand::Start) =
+                    (&settings.health_check, command)
+                {
+                    run_health_check(check, exec, &out)?;
+                }
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn parse_steps(contents: &str) -> Result<Vec<Step>, String> {
+    let root = toml::parse(contents)?;
+    for key in root.keys() {
+        if key != "step" {
+            return Err(format!("unknown section '{}' (expected [[step]])", key));
+        }
+    }
+    let steps = match root.get("step") {
+        Some(Value::Array(steps)) => steps,
+        Some(_) => return Err("'step' must be written as [[step]] tables".to_string()),
+        None => return Err("no [[step]] entries defined".to_string()),
+    };
+
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| match step {
+            Value::Table(table) => parse_step(table).map_err(|e| format!("step {}: {}", i + 1, e)),
+            _ => Err(format!("step {}: expected a table", i + 1)),
+        })
+        .collect()
+}
+
+fn parse_step(table: &Table) -> Result<Step, String> {
+    for key in table.keys() {
+        if !matches!(
+            key.as_str(),
+            "name"
+                | "compose"
+                | "services"
+                | "strategy"
+                | "unit"
+                | "command"
+                | "on"
+                | "health_check"
+        ) {
+            return Err(format!("unknown setting '{}'", key));
+        }
+    }
+
+    let string = |key: &str| -> Result<Option<String>, String> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(v) => Err(format!(
+                "'{}' must be a string, found {}",
+                key,
+                v.type_name()
+            )),
+        }
+    };
+    let strings = |key: &str| -> Result<Vec<String>, String> {
+        match table.get(key) {
+            None => Ok(Vec::new()),
+            Some(v) => v
+                .as_array()
+                .and_then(|items| items.iter().map(|i| i.as_str().map(String::from)).collect())
+                .ok_or_else(|| format!("'{}' must be an array of strings", key)),
+        }
+    };
+
+    let kinds = ["compose", "unit", "command"]
+        .iter()
+        .filter(|k| table.contains_key(**k))
+        .count();
+    if kinds != 1 {
+        return Err("each step needs exactly one of 'compose', 'unit' or 'command'".to_string());
+    }
+
+    let kind = if let Some(compose) = table.get("compose") {
+        let file = match compose {
+            Value::Boolean(true) => None,
+            Value::String(file) => Some(file.clone()),
+            _ => return Err("'compose' must be true or a compose file path".to_string()),
+        };
+        StepKind::Compose {
+            file,
+            services: strings("services")?,
+            strategy: string("strategy")?
+                .map(|name| ComposeStrategy::parse(&name))
+                .transpose()?,
+        }
+    } else if let Some(unit) = string("unit")? {
+        StepKind::Unit(unit)
+    } else {
+        let run = string("command")?.unwrap_or_default();
+        let on = match table.get("on") {
+            None => vec![TickleCommand::Tickle, TickleCommand::Start],
+            Some(_) => strings("on")?
+                .iter()
+                .map(|name| match name.as_str() {
+                    "tickle" => Ok(TickleCommand::Tickle),
+                    "start" => Ok(TickleCommand::Start),
+                    "stop" => Ok(TickleCommand::Stop),
+                    other => Err(format!(
+                        "unknown command '{}' in 'on' (expected tickle, start or stop)",
+                        other
+                    )),
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        StepKind::Command { run, on }
+    };
+
+    for key in ["services", "strategy"] {
+        if !matches!(kind, StepKind::Compose { .. }) && table.contains_key(key) {
+            return Err(format!("'{}' is only valid for compose steps", key));
+        }
+    }
+    if !matches!(kind, StepKind::Command { .. }) && table.contains_key("on") {
+        return Err("'on' is only valid for command steps".to_string());
+    }
+
+    Ok(Step {
+        name: string("name")?,
+        kind,
+        health_check: string("health_check")?,
+    })
+}
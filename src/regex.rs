@@ -0,0 +1,527 @@
+// src/regex.rs
+//! A small backtracking regular expression engine for matching log lines.
+//! It covers the syntax people reach for in a grep: literals, `.`, classes
+//! such as `[a-z]`, `[^0-9]` and `\d`/`\w`/`\s`, anchors (`^`, `$`, `\b`),
+//! groups with `|`, and `*`, `+`, `?` and `{n,m}` repetition (greedy, or
+//! lazy with a trailing `?`). A leading `(?i)` makes it case-insensitive.
+
+#[derive(Debug)]
+pub struct Regex {
+    node: Node,
+    ignore_case: bool,
+}
+
+#[derive(Debug)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug)]
+struct Class {
+    items: Vec<ClassItem>,
+    negated: bool,
+}
+
+#[derive(Debug)]
+enum ClassItem {
+    Range(char, char),
+    /// `\d`, `\w` or `\s`; `false` for the negated `\D`, `\W` and `\S`
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(low, high) => (low..=high).contains(&c),
+            ClassItem::Digit(positive) => c.is_ascii_digit() == positive,
+            ClassItem::Word(positive) => is_word(c) == positive,
+            ClassItem::Space(positive) => c.is_whitespace() == positive,
+        }
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, String> {
+        let (pattern, ignore_case) = match pattern.strip_prefix("(?i)") {
+            Some(rest) => (rest, true),
+            None => (pattern, false),
+        };
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+        };
+        let node = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(format!("unmatched ')' at position {}", parser.pos + 1));
+        }
+        Ok(Regex { node, ignore_case })
+    }
+
+    /// Whether the pattern matches anywhere in `text`
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        (0..=text.len()).any(|start| self.match_node(&self.node, &text, start, &mut |_| true))
+    }
+
+    fn same_char(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn class_matches(&self, class: &Class, c: char) -> bool {
+        let matches = |c: char| class.items.iter().any(|item| item.matches(c));
+        let found = matches(c)
+            || (self.ignore_case
+                && (c.to_lowercase().any(matches) || c.to_uppercase().any(matches)));
+        found != class.negated
+    }
+
+    /// Match `node` at `pos`, then hand the end position to `next` (the rest
+    /// of the pattern); backtracks until `next` accepts
+    fn match_node(
+        &self,
+        node: &Node,
+        text: &[char],
+        pos: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        match node {
+            Node::Char(c) => text
+                .get(pos)
+                .is_some_and(|&t| self.same_char(*c, t) && next(pos + 1)),
+            Node::Any => pos < text.len() && next(pos + 1),
+            Node::Class(class) => text
+                .get(pos)
+                .is_some_and(|&t| self.class_matches(class, t) && next(pos + 1)),
+            Node::Start => pos == 0 && next(pos),
+            Node::End => pos == text.len() && next(pos),
+            Node::WordBoundary(expected) => {
+                let before = pos > 0 && is_word(text[pos - 1]);
+                let after = pos < text.len() && is_word(text[pos]);
+                (before != after) == *expected && next(pos)
+            }
+            Node::Concat(nodes) => self.match_sequence(nodes, text, pos, next),
+            Node::Alternation(branches) => branches
+                .iter()
+                .any(|branch| self.match_node(branch, text, pos, next)),
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => match self.single_char(node) {
+                Some(matches) => {
+                    // Repeating one character needs no backtracking into the
+                    // node, so count the run and try each length
+                    let limit = max.map_or(text.len(), |max| text.len().min(pos + max));
+                    let run = text[pos..limit].iter().take_while(|&&c| matches(c)).count();
+                    if run < *min {
+                        return false;
+                    }
+                    if *greedy {
+                        (*min..=run).rev().any(|n| next(pos + n))
+                    } else {
+                        (*min..=run).any(|n| next(pos + n))
+                    }
+                }
+                None => self.match_repeat(node, *min, *max, *greedy, 0, text, pos, next),
+            },
+        }
+    }
+
+    /// A test for one character, when `node` always matches exactly one
+    fn single_char<'n>(&'n self, node: &'n Node) -> Option<Box<dyn Fn(char) -> bool + 'n>> {
+        match node {
+            Node::Char(c) => Some(Box::new(move |t| self.same_char(*c, t))),
+            Node::Any => Some(Box::new(|_| true)),
+            Node::Class(class) => Some(Box::new(move |t| self.class_matches(class, t))),
+            _ => None,
+        }
+    }
+
+    fn match_sequence(
+        &self,
+        nodes: &[Node],
+        text: &[char],
+        pos: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        match nodes.split_first() {
+            None => next(pos),
+            Some((first, rest)) => self.match_node(first, text, pos, &mut |end| {
+                self.match_sequence(rest, text, end, next)
+            }),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn match_repeat(
+        &self,
+        node: &Node,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+        count: usize,
+        text: &[char],
+        pos: usize,
+        next: &mut dyn FnMut(usize) -> bool,
+    ) -> bool {
+        let can_stop = count >= min;
+        let can_continue = max.is_none_or(|max| count < max);
+        if can_stop && !greedy && next(pos) {
+            return true;
+        }
+        // An iteration that matches nothing can't get any further
+        if can_continue
+            && self.match_node(node, text, pos, &mut |end| {
+                end != pos && self.match_repeat(node, min, max, greedy, count + 1, text, end, next)
+            })
+        {
+            return true;
+        }
+        if !can_stop && can_continue {
+            // Zero-width iterations still count towards the minimum
+            return self.match_node(node, text, pos, &mut |end| {
+                end == pos && self.match_repeat(node, min, max, greedy, min, text, end, next)
+            });
+        }
+        can_stop && greedy && next(pos)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("{} at position {}", message, self.pos + 1)
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Node::Alternation(branches)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                // Groups don't capture, so `(?:...)` is the same as `(...)`
+                if self.chars[self.pos..].starts_with(&['?', ':']) {
+                    self.pos += 2;
+                }
+                let node = self.alternation()?;
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                node
+            }
+            '[' => Node::Class(self.class()?),
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => {
+                self.pos -= 1;
+                return Err(self.error("nothing to repeat"));
+            }
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, String> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("trailing backslash"))?;
+        self.pos += 1;
+        Ok(match c {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            _ => match class_escape(c) {
+                Some(item) => Node::Class(Class {
+                    items: vec![item],
+                    negated: false,
+                }),
+                None => Node::Char(literal_escape(c)),
+            },
+        })
+    }
+
+    /// A bracket expression; the opening `[` has been consumed
+    fn class(&mut self) -> Result<Class, String> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unclosed character class"))?;
+            self.pos += 1;
+            // A `]` right after `[` or `[^` is a literal
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = if c == '\\' {
+                let escaped = self
+                    .peek()
+                    .ok_or_else(|| self.error("unclosed character class"))?;
+                self.pos += 1;
+                if let Some(item) = class_escape(escaped) {
+                    items.push(item);
+                    continue;
+                }
+                literal_escape(escaped)
+            } else {
+                c
+            };
+            let is_range = self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']');
+            if is_range && self.chars.get(self.pos + 1).is_some() {
+                self.pos += 1;
+                let mut high = self.chars[self.pos];
+                self.pos += 1;
+                if high == '\\' {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("unclosed character class"))?;
+                    self.pos += 1;
+                    high = literal_escape(escaped);
+                }
+                if high < low {
+                    return Err(self.error("invalid range in character class"));
+                }
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(ClassItem::Range(low, low));
+            }
+        }
+        Ok(Class { items, negated })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, String> {
+        let start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.braces() {
+                Some(bounds) => bounds,
+                // Not a valid `{n,m}`, so a literal brace
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if self.pos == start {
+            self.pos += 1;
+        }
+        if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) {
+            self.pos = start;
+            return Err(self.error("nothing to repeat"));
+        }
+        let greedy = !self.eat('?');
+        if matches!(self.peek(), Some('*' | '+' | '?')) {
+            return Err(self.error("nothing to repeat"));
+        }
+        // Backtracking through something like `(.+)+` takes exponential time
+        // on a line that doesn't match
+        if max.is_none_or(|max| max > 1) && has_unbounded_repeat(&atom) {
+            self.pos = start;
+            return Err(self.error("nested unbounded repetition"));
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// `{n}`, `{n,}` or `{n,m}`, consumed only when well formed
+    fn braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let rest: String = self.chars[self.pos + 1..].iter().collect();
+        let body = &rest[..rest.find('}')?];
+        let number = |s: &str| s.parse::<usize>().ok();
+        let bounds = match body.split_once(',') {
+            None => number(body).map(|n| (n, Some(n)))?,
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+        };
+        if bounds.1.is_some_and(|max| max < bounds.0) {
+            return None;
+        }
+        self.pos += body.chars().count() + 2;
+        Some(bounds)
+    }
+}
+
+/// Whether `node` contains a `*`, `+` or `{n,}`
+fn has_unbounded_repeat(node: &Node) -> bool {
+    match node {
+        Node::Repeat { node, max, .. } => max.is_none() || has_unbounded_repeat(node),
+        Node::Concat(nodes) | Node::Alternation(nodes) => nodes.iter().any(has_unbounded_repeat),
+        _ => false,
+    }
+}
+
+/// The class a `\d`-style escape stands for
+fn class_escape(c: char) -> Option<ClassItem> {
+    Some(match c {
+        'd' => ClassItem::Digit(true),
+        'D' => ClassItem::Digit(false),
+        'w' => ClassItem::Word(true),
+        'W' => ClassItem::Word(false),
+        's' => ClassItem::Space(true),
+        'S' => ClassItem::Space(false),
+        _ => return None,
+    })
+}
+
+/// The character an escape such as `\n` or `\.` stands for
+fn literal_escape(c: char) -> char {
+    match c {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Regex;
+    use std::time::{Duration, Instant};
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(is_match("[a-c]x", "the bx"));
+        assert!(!is_match("^[a-c]+$", "abcd"));
+        assert!(is_match("[^0-9]", "12a"));
+        assert!(!is_match("[^0-9]", "123"));
+        assert!(is_match("[]x]", "]"));
+        assert!(is_match("[a-]", "-"));
+        assert!(is_match(r"[\w.]+@", "ops.team@example.com"));
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert!(is_match(r"port \d+", "listening on port 8080"));
+        assert!(!is_match(r"port \d+", "listening on port http"));
+        assert!(is_match(r"\D\s\S", "a b"));
+        assert!(is_match(r"1\.5", "v1.5"));
+        assert!(!is_match(r"1\.5", "v125"));
+    }
+
+    #[test]
+    fn test_anchors() {
+        assert!(is_match("^Ready$", "Ready"));
+        assert!(!is_match("^Ready$", "Not Ready"));
+        assert!(!is_match("^Ready$", "Ready now"));
+        assert!(is_match(r"\berror\b", "an error occurred"));
+        assert!(!is_match(r"\berror\b", "no errors here"));
+        assert!(is_match(r"\Berr", "noerr"));
+    }
+
+    #[test]
+    fn test_ignore_case() {
+        assert!(is_match("(?i)started", "Server STARTED"));
+        assert!(!is_match("started", "Server STARTED"));
+        assert!(is_match("(?i)[a-z]+ ok", "HEALTH OK"));
+    }
+
+    #[test]
+    fn test_alternation_and_repetition() {
+        assert!(is_match("ready|listening", "now listening"));
+        assert!(is_match("^(GET|POST) /health", "POST /health 200"));
+        assert!(!is_match("^(GET|POST) /health", "PUT /health 200"));
+        assert!(is_match("^a{2,3}$", "aaa"));
+        assert!(!is_match("^a{2,3}$", "aaaa"));
+        assert!(is_match("^(ab)+c?$", "ababc"));
+        assert!(is_match("a.*?b", "a--b--b"));
+        assert!(is_match("x{", "x{"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for pattern in ["(a", "a)", "[a-", "*a", "a**", "^*", r"a\", "[z-a]"] {
+            assert!(
+                Regex::new(pattern).is_err(),
+                "{} should be rejected",
+                pattern
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_unbounded_repetition() {
+        for pattern in ["(.+)+x", "(a*)*", "(a|b+)*", "(x.*y){2,}", "(a+){1,50}"] {
+            let err = Regex::new(pattern).unwrap_err();
+            assert!(err.contains("nested unbounded repetition"), "{}", err);
+        }
+        // A group repeated at most once can't blow up
+        assert!(is_match("^(a+)?b", "aab"));
+        assert!(is_match("^(ab{1,3})+$", "abbabbb"));
+
+        // The single-character fast path keeps long lines quick
+        let line = "a".repeat(2000);
+        let started = Instant::now();
+        assert!(!is_match("a+b", &line));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
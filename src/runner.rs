@@ -0,0 +1,132 @@
+// src/runner.rs
+use std::fmt::Display;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::output::Output;
+
+/// Outcome of running one job against one target.
+pub struct JobResult {
+    pub target: String,
+    pub result: Result<(), String>,
+    pub elapsed: Duration,
+}
+
+/// Run `job` once per target, at most `parallel` at a time.
+///
+/// With a single worker, output goes straight to stdout. With several
+/// workers, each target's output is buffered and printed as one block when
+/// the target finishes, so lines from different targets never interleave.
+/// Results are returned in the same order as `targets`.
+pub fn run_jobs<T, F>(targets: &[T], parallel: usize, job: F) -> Vec<JobResult>
+where
+    T: Display + Sync,
+    F: Fn(&T, &Output) -> Result<(), String> + Sync,
+{
+    if parallel <= 1 || targets.len() <= 1 {
+        return targets
+            .iter()
+            .map(|target| {
+                if targets.len() > 1 {
+                    println!("\n── {} ──", target);
+                }
+                let started = Instant::now();
+                let result = job(target, &Output::Stdout);
+                if let Err(e) = &result {
+                    eprintln!("❌ Error: {}", e);
+                }
+                JobResult {
+                    target: target.to_string(),
+                    result,
+                    elapsed: started.elapsed(),
+                }
+            })
+            .collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let print_lock = Mutex::new(());
+    let slots: Mutex<Vec<Option<JobResult>>> = Mutex::new(targets.iter().map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..parallel.min(targets.len()) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(target) = targets.get(index) else {
+                        break;
+                    };
+
+                    let output = Output::buffered();
+                    let started = Instant::now();
+                    let result = job(target, &output);
+                    let elapsed = started.elapsed();
+
+                    {
+                        let _guard = print_lock.lock().unwrap_or_else(|e| e.into_inner());
+                        println!("\n── {} ──", target);
+                        for line in output.into_lines() {
+                            println!("{}", line);
+                        }
+                        if let Err(e) = &result {
+                            eprintln!("❌ Error: {}", e);
+                        }
+                    }
+
+                    slots.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(JobResult {
+                        target: target.to_string(),
+                        result,
+                        elapsed,
+                    });
+                }
+            });
+        }
+    });
+
+    slots
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Print an aggregated result table for a multi-target run
+pub fn print_summary(results: &[JobResult]) {
+    let width = results
+        .iter()
+        .map(|r| r.target.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Target".len());
+
+    println!("\n📋 Summary\n");
+    println!(
+        "{:<width$} | {:<7} | {:>7} | Details",
+        "Target", "Status", "Time"
+    );
+    println!("{}", "-".repeat(width + 32));
+    for r in results {
+        let (status, details) = match &r.result {
+            Ok(()) => ("SUCCESS", String::new()),
+            Err(e) => ("FAILED", e.lines().next().unwrap_or("").to_string()),
+        };
+        println!(
+            "{:<width$} | {:<7} | {:>6.1}s | {}",
+            r.target,
+            status,
+            r.elapsed.as_secs_f64(),
+            details
+        );
+    }
+
+    let failed = results.iter().filter(|r| r.result.is_err()).count();
+    println!(
+        "\nTotal: {}, succeeded: {}, failed: {}",
+        results.len(),
+        results.len() - failed,
+        failed
+    );
+}
@@ -0,0 +1,147 @@
+// src/stacks.rs
+//! Registry of named compose stacks, so `stack:<name>` works from any
+//! directory. Stored in `~/.tickle/stacks.toml`:
+//!
+//! ```toml
+//! [stacks]
+//! myapp = "/srv/myapp/docker-compose.yml"
+//! ```
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::compose::{self, ComposeStack};
+use crate::output::Output;
+use crate::process::ExecOptions;
+use crate::target::Target;
+use crate::toml::{self, Value};
+
+pub struct StackRegistry {
+    path: PathBuf,
+    stacks: BTreeMap<String, PathBuf>,
+}
+
+impl StackRegistry {
+    /// Load the registry; a missing file is an empty registry
+    pub fn load() -> Result<Self, String> {
+        let home =
+            env::var("HOME").map_err(|_| "Could not determine HOME directory".to_string())?;
+        let path = PathBuf::from(home).join(".tickle").join("stacks.toml");
+        let mut stacks = BTreeMap::new();
+        if path.exists() {
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let root = toml::parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))?;
+            if let Some(table) = root.get("stacks").and_then(Value::as_table) {
+                for (name, file) in table {
+                    let file = file.as_str().ok_or_else(|| {
+                        format!("{}: stacks.{} must be a path", path.display(), name)
+                    })?;
+                    stacks.insert(name.clone(), PathBuf::from(file));
+                }
+            }
+        }
+        Ok(StackRegistry { path, stacks })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let mut contents =
+            String::from("# Compose stacks registered with `tickle stack add`\n[stacks]\n");
+        // Quoted, so a name with a '.' stays one key instead of a table
+        for (name, file) in &self.stacks {
+            contents.push_str(&format!(
+                "{} = {}\n",
+                quote(name),
+                quote(&file.display().to_string())
+            ));
+        }
+        fs::write(&self.path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    /// Compose file of a registered stack
+    pub fn get(&self, name: &str) -> Result<&Path, String> {
+        self.stacks.get(name).map(PathBuf::as_path).ok_or_else(|| {
+            if self.stacks.is_empty() {
+                format!(
+                    "Unknown stack '{}' (no stacks registered; see `tickle stack add`)",
+                    name
+                )
+            } else {
+                format!(
+                    "Unknown stack '{}' (registered stacks: {})",
+                    name,
+                    self.stacks.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            }
+        })
+    }
+
+    /// Register `location` (a compose file, or a directory holding one)
+    /// under `name`, replacing any stack of that name
+    pub fn add(&mut self, name: &str, location: &Path) -> Result<PathBuf, String> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        {
+            return Err(format!(
+                "Invalid stack name '{}' (use letters, digits, '-', '_' and '.')",
+                name
+            ));
+        }
+        let location = location
+            .canonicalize()
+            .map_err(|e| format!("{}: {}", location.display(), e))?;
+        let file = if location.is_dir() {
+            compose::compose_file_in(&location)
+                .ok_or_else(|| format!("No compose file found in {}", location.display()))?
+        } else {
+            location
+        };
+        self.stacks.insert(name.to_string(), file.clone());
+        self.save()?;
+        Ok(file)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        self.get(name)?;
+        self.stacks.remove(name);
+        self.save()
+    }
+
+    /// Print every stack with its current state
+    pub fn list(&self) {
+        if self.stacks.is_empty() {
+            println!("📦 No stacks registered. Add one with `tickle stack add <name> <dir>`.");
+            return;
+        }
+        println!("📦 Registered stacks ({})\n", self.path.display());
+        println!("{:<20} {:<10} Compose file", "Name", "State");
+        println!("{}", "-".repeat(70));
+        for (name, file) in &self.stacks {
+            let state = if file.exists() {
+                let stack =
+                    ComposeStack::new(file, Vec::new(), &Output::Stdout, ExecOptions::default());
+                match stack.state() {
+                    Ok(state) => format!("{:?}", state),
+                    Err(_) => "Unknown".to_string(),
+                }
+            } else {
+                "Missing".to_string()
+            };
+            println!("{:<20} {:<10} {}", name, state, file.display());
+        }
+    }
+}
+
+/// A TOML basic string
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,378 @@
+// src/target.rs
+use std::fs;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+use crate::compose::{ComposeOptions, ComposeStack};
+use crate::config::{Config, ServiceConfig};
+use crate::container::ContainerTarget;
+use crate::custom::CustomTarget;
+use crate::logs::LogOptions;
+use crate::output::Output;
+use crate::process::{self, ExecOptions};
+use crate::stacks::StackRegistry;
+use crate::{RestartStrategy, ServiceManager, ServiceState, TickleCommand};
+
+/// Pause between attempts when a target has `retries` configured
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Something tickle can restart, start, stop and follow the logs of
+pub trait Target {
+    /// Name used in progress messages
+    fn name(&self) -> String;
+    fn state(&self) -> Result<ServiceState, String>;
+    /// Restart; a `strategy` overrides the target's own choice where it has one
+    fn restart(&self, strategy: Option<RestartStrategy>) -> Result<(), String>;
+    fn start(&self) -> Result<(), String>;
+    fn stop(&self) -> Result<(), String>;
+    /// Extra detail to record in history for the last operation
+    fn history_note(&self) -> Option<String> {
+        None
+    }
+    /// Print a read-only overview for `tickle status`
+    fn print_status(&self) -> Result<(), String> {
+        println!("📊 {}: {:?}", self.name(), self.state()?);
+        Ok(())
+    }
+    /// The command that shows this target's logs; `--grep` is left to tickle
+    fn logs_command(&self, options: &LogOptions) -> Result<Command, String>;
+    /// Log options selecting what the target logs from now on, taken before
+    /// an operation for `--show-logs`; an error if the target can't tell
+    fn new_logs(&self) -> Result<LogOptions, String> {
+        Ok(LogOptions::from_now())
+    }
+}
+
+/// A systemd unit
+pub struct SystemdUnit<'a> {
+    name: String,
+    manager: ServiceManager<'a>,
+}
+
+impl<'a> SystemdUnit<'a> {
+    /// `web.container` and `web.pod` name the units Quadlet generates from
+    /// those files, `web.service` and `web-pod.service`
+    pub fn new(name: &str, out: &'a Output, exec: ExecOptions) -> Self {
+        let name = if let Some(stem) = name.strip_suffix(".container") {
+            format!("{}.service", stem)
+        } else if let Some(stem) = name.strip_suffix(".pod") {
+            format!("{}-pod.service", stem)
+        } else {
+            name.to_string()
+        };
+        SystemdUnit {
+            name,
+            manager: ServiceManager::new(out).with_exec(exec),
+        }
+    }
+
+    /// Tell the user when the unit comes from a Quadlet file, since edits
+    /// to the unit itself would be overwritten
+    fn note_quadlet(&self) {
+        if let Some(source) = self.manager.quadlet_source(&self.name) {
+            self.manager.out.line(format!(
+                "ℹ️  {} is generated by Quadlet from {}; edit that file and run `systemctl daemon-reload` to change it",
+                self.name, source
+            ));
+        }
+    }
+}
+
+impl Target for SystemdUnit<'_> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn state(&self) -> Result<ServiceState, String> {
+        self.manager.get_service_state(&self.name)
+    }
+
+    fn restart(&self, strategy: Option<RestartStrategy>) -> Result<(), String> {
+        self.manager.check_systemctl_available()?;
+        self.note_quadlet();
+        self.manager.tickle_service(&self.name, strategy)
+    }
+
+    fn start(&self) -> Result<(), String> {
+        self.manager.check_systemctl_available()?;
+        self.note_quadlet();
+        self.manager.start_service(&self.name)
+    }
+
+    fn stop(&self) -> Result<(), String> {
+        self.manager.check_systemctl_available()?;
+        self.note_quadlet();
+        self.manager.stop_service(&self.name)
+    }
+
+    /// State, main PID, memory use and when the unit last started
+    fn print_status(&self) -> Result<(), String> {
+        let properties = self.manager.unit_properties(
+            &self.name,
+            &[
+                "LoadState",
+                "ActiveState",
+                "SubState",
+                "MainPID",
+                "MemoryCurrent",
+                "ActiveEnterTimestamp",
+                "ActiveEnterTimestampMonotonic",
+            ],
+        )?;
+        let get = |key: &str| properties.get(key).map(String::as_str).unwrap_or_default();
+        if get("LoadState") == "not-found" {
+            return Err("no such systemd unit".to_string());
+        }
+
+        let pid = match get("MainPID") {
+            "" | "0" => "-".to_string(),
+            pid => pid.to_string(),
+        };
+        // systemd reports an unknown value as the maximum u64
+        let memory = match get("MemoryCurrent").parse::<u64>() {
+            Ok(bytes) if bytes != u64::MAX => format_bytes(bytes),
+            _ => "-".to_string(),
+        };
+        let started = match get("ActiveEnterTimestamp") {
+            "" => "-".to_string(),
+            timestamp => match since_boot_offset(get("ActiveEnterTimestampMonotonic")) {
+                Some(elapsed) => format!("{} ({} ago)", timestamp, format_elapsed(elapsed)),
+                None => timestamp.to_string(),
+            },
+        };
+
+        println!("📊 {}", self.name);
+        println!("   State:   {} ({})", get("ActiveState"), get("SubState"));
+        println!("   PID:     {}", pid);
+        println!("   Memory:  {}", memory);
+        println!("   Started: {}", started);
+        Ok(())
+    }
+
+    /// `journalctl -u SERVICE` with the filters journald understands
+    fn logs_command(&self, options: &LogOptions) -> Result<Command, String> {
+        let mut command = Command::new("journalctl");
+        if options.follow {
+            command.arg("-f");
+        }
+        command.args(["-u", &self.name]);
+        if let Some(since) = &options.since {
+            command.args(["--since", &since.for_journal()]);
+        }
+        if let Some(until) = &options.until {
+            command.args(["--until", &until.for_journal()]);
+        }
+        if let Some(lines) = options.lines {
+            command.args(["-n", &lines.to_string()]);
+        }
+        if let Some(priority) = &options.priority {
+            command.args(["-p", priority]);
+        }
+        if let Some(cursor) = &options.after_cursor {
+            command.args(["--after-cursor", cursor]);
+        }
+        if !options.follow {
+            command.arg("--no-pager");
+        }
+        Ok(command)
+    }
+
+    /// The journal's current cursor, which unlike a timestamp can't let in
+    /// lines from the same second before the operation
+    fn new_logs(&self) -> Result<LogOptions, String> {
+        let cursor = Command::new("journalctl")
+            .args(["-n", "0", "--show-cursor", "--no-pager", "-q"])
+            .output()
+            .ok()
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find_map(|line| line.strip_prefix("-- cursor: ").map(str::to_string))
+            });
+        Ok(match cursor {
+            Some(cursor) => LogOptions {
+                after_cursor: Some(cursor),
+                ..LogOptions::default()
+            },
+            None => LogOptions::from_now(),
+        })
+    }
+}
+
+/// Time elapsed since a moment given as microseconds on the monotonic
+/// clock (time since boot), as systemd's `*TimestampMonotonic` properties are
+fn since_boot_offset(monotonic_usec: &str) -> Option<Duration> {
+    let then = monotonic_usec
+        .parse::<u64>()
+        .ok()
+        .filter(|&usec| usec > 0)?;
+    let uptime = fs::read_to_string("/proc/uptime").ok()?;
+    let now = uptime.split_whitespace().next()?.parse::<f64>().ok()?;
+    Duration::from_secs_f64(now).checked_sub(Duration::from_micros(then))
+}
+
+/// A duration in its two largest units, e.g. `3h 12m`
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// A byte count the way `systemctl status` shows memory, e.g. `12.5M`
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+/// Address prefixes for standalone containers, and the engine CLI for each
+const CONTAINER_PREFIXES: [(&str, &str); 2] = [("container", "docker"), ("podman", "podman")];
+
+/// Whether `spec` names a typed target (`<type>:<name>`) rather than a
+/// systemd unit or compose service
+pub fn is_typed(spec: &str, config: &Config) -> bool {
+    spec.split_once(':').is_some_and(|(type_name, _)| {
+        config.custom_type(type_name).is_some()
+            || type_name == "stack"
+            || CONTAINER_PREFIXES
+                .iter()
+                .any(|(prefix, _)| *prefix == type_name)
+    })
+}
+
+/// Build the target for a name from the command line: `<type>:<name>` for a
+/// custom type defined in the config, a standalone container (`container:`
+/// for docker, `podman:`) or a registered compose stack (`stack:`),
+/// otherwise a systemd unit
+pub fn make_target<'a>(
+    spec: &str,
+    config: &Config,
+    compose: &ComposeOptions,
+    out: &'a Output,
+    exec: ExecOptions,
+) -> Result<Box<dyn Target + 'a>, String> {
+    if let Some((type_name, name)) = spec.split_once(':') {
+        if let Some(kind) = config.custom_type(type_name) {
+            return Ok(Box::new(CustomTarget::new(
+                type_name,
+                name,
+                kind.clone(),
+                out,
+                exec,
+            )));
+        }
+        if let Some((prefix, engine)) = CONTAINER_PREFIXES.iter().find(|(p, _)| *p == type_name) {
+            return Ok(Box::new(
+                ContainerTarget::new(prefix, engine, name, out, exec)
+                    .with_context(compose.context.as_deref()),
+            ));
+        }
+        if type_name == "stack" {
+            let registry = StackRegistry::load()?;
+            let file = registry.get(name)?;
+            return Ok(Box::new(
+                ComposeStack::new(file, Vec::new(), out, exec).with_options(compose),
+            ));
+        }
+    }
+    Ok(Box::new(SystemdUnit::new(spec, out, exec)))
+}
+
+/// Run a user supplied health check command through `sh -c`
+pub fn run_health_check(health_check: &str, exec: ExecOptions, out: &Output) -> Result<(), String> {
+    out.line(format!("🩺 Running health check: {}", health_check));
+
+    let output = process::run(Command::new("sh").args(["-c", health_check]), exec, out)
+        .map_err(|e| format!("Failed to run health check: {}", e))?;
+
+    if output.status.success() {
+        out.line("✅ Health check passed");
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let detail = if stderr.trim().is_empty() {
+            stdout.trim().to_string()
+        } else {
+            stderr.trim().to_string()
+        };
+        Err(format!("Health check failed: {}", detail))
+    }
+}
+
+/// Run one start/stop/tickle against a target using its effective settings,
+/// retrying and health-checking as configured
+pub fn run_target_command(
+    command: &TickleCommand,
+    target: &dyn Target,
+    settings: &ServiceConfig,
+    force: bool,
+    exec: ExecOptions,
+    out: &Output,
+) -> Result<(), String> {
+    if settings.protected == Some(true) && !force {
+        return Err(format!(
+            "{} is protected in the tickle config; pass --force to {} it anyway",
+            target.name(),
+            command.name()
+        ));
+    }
+
+    let attempts = settings.retries.unwrap_or(0).saturating_add(1);
+    let mut attempt = 1;
+    loop {
+        let result = match command {
+            TickleCommand::Tickle => target.restart(settings.strategy),
+            TickleCommand::Start => target.start(),
+            TickleCommand::Stop => target.stop(),
+            TickleCommand::History
+            | TickleCommand::Stack
+            | TickleCommand::Inspect
+            | TickleCommand::Status
+            | TickleCommand::Logs => unreachable!(),
+        };
+        // Health checks only make sense when the target should be running
+        let result = match (&settings.health_check, command) {
+            (Some(check), TickleCommand::Tickle | TickleCommand::Start) => {
+                result.and_then(|_| run_health_check(check, exec, out))
+            }
+            _ => result,
+        };
+
+        match result {
+            Err(e) if attempt < attempts => {
+                out.line(format!(
+                    "⚠️  Attempt {}/{} failed: {}",
+                    attempt, attempts, e
+                ));
+                thread::sleep(RETRY_DELAY);
+                attempt += 1;
+                out.line(format!(
+                    "🔁 Retrying {} (attempt {}/{})...",
+                    target.name(),
+                    attempt,
+                    attempts
+                ));
+            }
+            result => return result,
+        }
+    }
+}
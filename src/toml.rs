@@ -0,0 +1,430 @@
+// src/toml.rs
+//! A small parser for the subset of TOML used by tickle's config files.
+//!
+//! Supported: comments, `[table]` and `[[array.of.tables]]` headers with bare
+//! or quoted keys, dotted keys, basic and literal strings, integers, floats,
+//! booleans, (multi-line) arrays and inline tables. Dates and multi-line
+//! strings are not supported.
+
+use std::collections::BTreeMap;
+
+pub type Table = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self {
+            Value::Table(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Human readable name of the value's type, for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::Integer(_) => "integer",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "boolean",
+            Value::Array(_) => "array",
+            Value::Table(_) => "table",
+        }
+    }
+}
+
+/// Parse a TOML document into its root table.
+/// Errors are reported as `line N: message`.
+pub fn parse(input: &str) -> Result<Table, String> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    parser
+        .document()
+        .map_err(|e| format!("line {}: {}", parser.line, e))
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(c) => format!("expected '{}', found '{}'", expected, c),
+                None => format!("expected '{}', found end of file", expected),
+            })
+        }
+    }
+
+    /// Skip spaces and tabs on the current line
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.bump();
+        }
+    }
+
+    /// Skip whitespace, newlines and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ') | Some('\t') | Some('\r') | Some('\n') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_comment(),
+                _ => break,
+            }
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    /// Require the rest of the line to be blank or a comment
+    fn end_of_line(&mut self) -> Result<(), String> {
+        self.skip_blank();
+        if self.peek() == Some('#') {
+            self.skip_comment();
+        }
+        self.eat('\r');
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.bump();
+                Ok(())
+            }
+            Some(c) => Err(format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Table, String> {
+        let mut root = Table::new();
+        // Path of the table that key/value pairs currently go into
+        let mut current: Vec<String> = Vec::new();
+
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.bump();
+                    let is_array = self.eat('[');
+                    self.skip_blank();
+                    let path = self.key_path()?;
+                    self.skip_blank();
+                    self.expect(']')?;
+                    if is_array {
+                        self.expect(']')?;
+                        push_array_table(&mut root, &path)?;
+                    } else {
+                        define_table(&mut root, &path)?;
+                    }
+                    self.end_of_line()?;
+                    current = path;
+                }
+                Some(_) => {
+                    let key = self.key_path()?;
+                    self.skip_blank();
+                    self.expect('=')?;
+                    self.skip_blank();
+                    let value = self.value()?;
+                    self.end_of_line()?;
+                    let table = table_at(&mut root, &current)?;
+                    insert_dotted(table, &key, value)?;
+                }
+            }
+        }
+
+        Ok(root)
+    }
+
+    /// Parse a possibly dotted key such as `services."php8.2-fpm"`
+    fn key_path(&mut self) -> Result<Vec<String>, String> {
+        let mut path = vec![self.key()?];
+        loop {
+            self.skip_blank();
+            if !self.eat('.') {
+                break;
+            }
+            self.skip_blank();
+            path.push(self.key()?);
+        }
+        Ok(path)
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some('"') => self.basic_string(),
+            Some('\'') => self.literal_string(),
+            _ => {
+                let mut key = String::new();
+                while let Some(c) = self.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                        key.push(c);
+                        self.bump();
+                    } else {
+                        break;
+                    }
+                }
+                if key.is_empty() {
+                    Err(match self.peek() {
+                        Some(c) => format!("expected a key, found '{}'", c),
+                        None => "expected a key, found end of file".to_string(),
+                    })
+                } else {
+                    Ok(key)
+                }
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) => self.scalar(),
+            None => Err("expected a value, found end of file".to_string()),
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err("unterminated string".to_string()),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some('r') => s.push('\r'),
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some(c) => return Err(format!("unsupported escape '\\{}'", c)),
+                    None => return Err("unterminated string".to_string()),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, String> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err("unterminated string".to_string()),
+                Some('\'') => return Ok(s),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        loop {
+            self.skip_trivia();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_trivia();
+            if self.eat(']') {
+                return Ok(Value::Array(items));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, String> {
+        self.expect('{')?;
+        let mut table = Table::new();
+        self.skip_blank();
+        if self.eat('}') {
+            return Ok(Value::Table(table));
+        }
+        loop {
+            self.skip_blank();
+            let key = self.key_path()?;
+            self.skip_blank();
+            self.expect('=')?;
+            self.skip_blank();
+            let value = self.value()?;
+            insert_dotted(&mut table, &key, value)?;
+            self.skip_blank();
+            if self.eat('}') {
+                return Ok(Value::Table(table));
+            }
+            self.expect(',')?;
+        }
+    }
+
+    /// Booleans and numbers
+    fn scalar(&mut self) -> Result<Value, String> {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '_' | '.') {
+                word.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        match word.as_str() {
+            "true" => return Ok(Value::Boolean(true)),
+            "false" => return Ok(Value::Boolean(false)),
+            "" => {
+                return Err(format!(
+                    "expected a value, found '{}'",
+                    self.peek().unwrap_or(' ')
+                ));
+            }
+            _ => {}
+        }
+        let digits = word.replace('_', "");
+        if let Ok(n) = digits.parse::<i64>() {
+            return Ok(Value::Integer(n));
+        }
+        if digits.contains(['.', 'e', 'E'])
+            && let Ok(f) = digits.parse::<f64>()
+        {
+            return Ok(Value::Float(f));
+        }
+        Err(format!("invalid value '{}'", word))
+    }
+}
+
+/// Create (or re-open, if it was only implicitly created) the table at `path`
+fn define_table(root: &mut Table, path: &[String]) -> Result<(), String> {
+    table_at(root, path).map(|_| ())
+}
+
+/// Append a new empty table to the array of tables at `path`
+fn push_array_table(root: &mut Table, path: &[String]) -> Result<(), String> {
+    let (last, parents) = path.split_last().expect("key paths are never empty");
+    let parent = table_at(root, parents)?;
+    match parent
+        .entry(last.clone())
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        Value::Array(items) => {
+            items.push(Value::Table(Table::new()));
+            Ok(())
+        }
+        other => Err(format!(
+            "'{}' is already defined as a {}",
+            last,
+            other.type_name()
+        )),
+    }
+}
+
+/// Walk to the table at `path`, creating missing tables. When a path segment
+/// names an array of tables, the walk continues into its last element.
+fn table_at<'a>(root: &'a mut Table, path: &[String]) -> Result<&'a mut Table, String> {
+    let mut table = root;
+    for key in path {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| Value::Table(Table::new()));
+        table = match entry {
+            Value::Table(t) => t,
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Table(t)) => t,
+                _ => return Err(format!("'{}' is not a table", key)),
+            },
+            other => {
+                return Err(format!(
+                    "'{}' is already defined as a {}",
+                    key,
+                    other.type_name()
+                ));
+            }
+        };
+    }
+    Ok(table)
+}
+
+fn insert_dotted(table: &mut Table, key: &[String], value: Value) -> Result<(), String> {
+    let (last, parents) = key.split_last().expect("key paths are never empty");
+    let table = table_at(table, parents)?;
+    if table.contains_key(last) {
+        return Err(format!("duplicate key '{}'", last));
+    }
+    table.insert(last.clone(), value);
+    Ok(())
+}
@@ -0,0 +1,678 @@
+// src/yaml.rs
+//! A small parser for the subset of YAML found in compose files.
+//!
+//! Supported: comments, block mappings and sequences (including sequences
+//! written at the same indentation as their key), flow collections, plain,
+//! single- and double-quoted scalars, literal (`|`) and folded (`>`) block
+//! scalars, anchors, aliases and `<<` merge keys. Scalars are kept as
+//! strings. Tags, quoted scalars spanning lines, complex keys and
+//! multi-document streams are not supported; `unsupported` spots the first
+//! two so callers can leave such files to a full parser.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    String(String),
+    Sequence(Vec<Value>),
+    /// Entries in document order
+    Mapping(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_mapping(&self) -> Option<&[(String, Value)]> {
+        match self {
+            Value::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Value of `key` in a mapping
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_mapping()?
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Human readable name of the value's type, for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::String(_) => "string",
+            Value::Sequence(_) => "sequence",
+            Value::Mapping(_) => "mapping",
+        }
+    }
+}
+
+/// Parse a YAML document. Errors are reported as `line N: message`.
+pub fn parse(input: &str) -> Result<Value, String> {
+    let raw: Vec<&str> = input.lines().collect();
+    let lines = raw
+        .iter()
+        .enumerate()
+        .map(|(i, text)| Line::new(i + 1, text))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut parser = Parser {
+        raw,
+        lines,
+        pos: 0,
+        anchors: HashMap::new(),
+    };
+    let value = parser.block(0)?;
+    match parser.peek() {
+        Some(line) => Err(format!("line {}: unexpected indentation", line.number)),
+        None => Ok(value),
+    }
+}
+
+/// The first thing in `input` this parser would read differently from
+/// YAML proper, if any: a tag such as `!reset`, or a quoted scalar that
+/// continues on the next line
+pub fn unsupported(input: &str) -> Option<String> {
+    let mut block_scalar = None;
+    for (i, raw) in input.lines().enumerate() {
+        let line = Line::new(i + 1, raw).ok()?;
+        if line.text.is_empty() {
+            continue;
+        }
+        // The lines of a block scalar are its content
+        if block_scalar.is_some_and(|indent| line.indent > indent) {
+            continue;
+        }
+        block_scalar = None;
+
+        let mut value = line.text.as_str();
+        loop {
+            if is_sequence_item(value) {
+                value = value[1..].trim_start();
+            } else if let Some((_, rest)) = split_key(value) {
+                value = rest;
+            } else {
+                break;
+            }
+        }
+        let (_, value) = take_anchor(value);
+        match value.chars().next() {
+            Some('!') => {
+                let tag = value.split_whitespace().next().unwrap_or(value);
+                return Some(format!("the tag `{}` on line {}", tag, line.number));
+            }
+            Some('|' | '>') => block_scalar = Some(line.indent),
+            Some(quote @ ('"' | '\'')) if Flow::new(value, line.number).quoted(quote).is_err() => {
+                return Some(format!(
+                    "a quoted string continuing past line {}",
+                    line.number
+                ));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// One line of input with its comment removed
+struct Line {
+    number: usize,
+    indent: usize,
+    /// Content after the indentation; empty for blank and comment lines
+    text: String,
+}
+
+impl Line {
+    fn new(number: usize, raw: &str) -> Result<Line, String> {
+        let content = raw.trim_start_matches(' ');
+        let indent = raw.len() - content.len();
+        let text = strip_comment(content).trim_end().to_string();
+        if text.starts_with('\t') {
+            return Err(format!(
+                "line {}: tabs are not allowed for indentation",
+                number
+            ));
+        }
+        // Document markers and directives carry nothing we need
+        let text = if indent == 0 && (text == "---" || text == "..." || text.starts_with('%')) {
+            String::new()
+        } else {
+            text
+        };
+        Ok(Line {
+            number,
+            indent,
+            text,
+        })
+    }
+}
+
+/// Remove a trailing `# comment`, leaving `#` inside quotes alone
+fn strip_comment(text: &str) -> &str {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut quote = None;
+    let mut i = 0;
+    while let Some(&(offset, c)) = chars.get(i) {
+        let prev = if i == 0 { ' ' } else { chars[i - 1].1 };
+        match quote {
+            Some('"') if c == '\\' => i += 1,
+            // `''` is an escaped quote inside a single-quoted string
+            Some('\'') if c == '\'' && chars.get(i + 1).is_some_and(|&(_, n)| n == '\'') => i += 1,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '#' && prev.is_whitespace() => return &text[..offset],
+            None if (c == '"' || c == '\'') && (prev.is_whitespace() || "[{,:-".contains(prev)) => {
+                quote = Some(c)
+            }
+            None => {}
+        }
+        i += 1;
+    }
+    text
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Split `key: rest` into its key and the text after the colon
+fn split_key(text: &str) -> Option<(String, &str)> {
+    if let Some(quote @ ('"' | '\'')) = text.chars().next() {
+        let mut flow = Flow::new(text, 0);
+        let key = flow.quoted(quote).ok()?;
+        let rest = text[flow.byte_pos()..].trim_start();
+        let rest = rest.strip_prefix(':')?;
+        return (rest.is_empty() || rest.starts_with(' ')).then(|| (key, rest.trim_start()));
+    }
+    if text.starts_with(['[', '{', '&', '*', '|', '>']) || is_sequence_item(text) {
+        return None;
+    }
+    let (key, rest) = match text.find(": ") {
+        Some(i) => (&text[..i], &text[i + 2..]),
+        None => (text.strip_suffix(':')?, ""),
+    };
+    let key = key.trim_end();
+    (!key.is_empty()).then(|| (key.to_string(), rest.trim_start()))
+}
+
+/// Split a leading `&anchor` from a value
+fn take_anchor(text: &str) -> (Option<String>, &str) {
+    match text.strip_prefix('&') {
+        Some(rest) => {
+            let end = rest.find(' ').unwrap_or(rest.len());
+            (Some(rest[..end].to_string()), rest[end..].trim_start())
+        }
+        None => (None, text),
+    }
+}
+
+fn plain_scalar(text: &str) -> Value {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => Value::Null,
+        _ => Value::String(text.to_string()),
+    }
+}
+
+struct Parser<'a> {
+    raw: Vec<&'a str>,
+    lines: Vec<Line>,
+    pos: usize,
+    anchors: HashMap<String, Value>,
+}
+
+impl Parser<'_> {
+    /// The next line with content, skipping blank and comment lines
+    fn peek(&mut self) -> Option<&Line> {
+        while self.lines.get(self.pos)?.text.is_empty() {
+            self.pos += 1;
+        }
+        self.lines.get(self.pos)
+    }
+
+    /// A block node whose lines are indented by at least `min_indent`
+    fn block(&mut self, min_indent: usize) -> Result<Value, String> {
+        let Some(line) = self.peek() else {
+            return Ok(Value::Null);
+        };
+        if line.indent < min_indent {
+            return Ok(Value::Null);
+        }
+        let (indent, number) = (line.indent, line.number);
+        if is_sequence_item(&line.text) {
+            return self.sequence(indent);
+        }
+        if split_key(&line.text).is_some() {
+            return self.mapping(indent);
+        }
+        let text = line.text.clone();
+        self.pos += 1;
+        self.inline(&text, indent.saturating_sub(1), number)
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Value, String> {
+        let mut entries: Vec<(String, Value)> = Vec::new();
+        let mut merged: Vec<(String, Value)> = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent < indent {
+                break;
+            }
+            let number = line.number;
+            if line.indent > indent {
+                return Err(format!("line {}: unexpected indentation", number));
+            }
+            let (key, rest) = split_key(&line.text)
+                .map(|(key, rest)| (key, rest.to_string()))
+                .ok_or_else(|| format!("line {}: expected `key: value`", number))?;
+            self.pos += 1;
+
+            let value = self.value(&rest, indent, number, true)?;
+            if key == "<<" {
+                let sources = match value {
+                    Value::Sequence(items) => items,
+                    value => vec![value],
+                };
+                for source in sources {
+                    let Value::Mapping(source) = source else {
+                        return Err(format!("line {}: `<<` needs a mapping to merge", number));
+                    };
+                    for (k, v) in source {
+                        if !merged.iter().any(|(m, _)| *m == k) {
+                            merged.push((k, v));
+                        }
+                    }
+                }
+            } else if entries.iter().any(|(k, _)| *k == key) {
+                return Err(format!("line {}: duplicate key '{}'", number, key));
+            } else {
+                entries.push((key, value));
+            }
+        }
+        for (k, v) in merged {
+            if !entries.iter().any(|(e, _)| *e == k) {
+                entries.push((k, v));
+            }
+        }
+        Ok(Value::Mapping(entries))
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Value, String> {
+        let mut items = Vec::new();
+        while let Some(line) = self.peek() {
+            if line.indent != indent || !is_sequence_item(&line.text) {
+                if line.indent > indent {
+                    return Err(format!("line {}: unexpected indentation", line.number));
+                }
+                break;
+            }
+            let number = line.number;
+            let rest = line.text[1..].trim_start().to_string();
+            let offset = line.text.len() - rest.len();
+
+            // `- key: value` and `- - item` start a nested block on this line
+            if split_key(&rest).is_some() || is_sequence_item(&rest) {
+                let line = &mut self.lines[self.pos];
+                line.indent += offset;
+                line.text = rest;
+                items.push(self.block(indent + offset)?);
+            } else {
+                self.pos += 1;
+                items.push(self.value(&rest, indent, number, false)?);
+            }
+        }
+        Ok(Value::Sequence(items))
+    }
+
+    /// The value after `key:` or `-`, which may continue on following lines
+    fn value(
+        &mut self,
+        text: &str,
+        indent: usize,
+        number: usize,
+        compact_sequence: bool,
+    ) -> Result<Value, String> {
+        let (anchor, text) = take_anchor(text);
+        let value = if text.is_empty() {
+            match self.peek() {
+                Some(line) if line.indent > indent => {
+                    let indent = line.indent;
+                    self.block(indent)?
+                }
+                Some(line)
+                    if compact_sequence
+                        && line.indent == indent
+                        && is_sequence_item(&line.text) =>
+                {
+                    self.sequence(indent)?
+                }
+                _ => Value::Null,
+            }
+        } else {
+            self.inline(text, indent, number)?
+        };
+        if let Some(anchor) = anchor {
+            self.anchors.insert(anchor, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// A value that starts on the current line
+    fn inline(&mut self, text: &str, indent: usize, number: usize) -> Result<Value, String> {
+        if let Some(header) = text.strip_prefix(['|', '>']) {
+            return Ok(Value::String(self.block_scalar(
+                text.starts_with('>'),
+                header,
+                indent,
+                number,
+            )?));
+        }
+        if text.starts_with(['[', '{']) {
+            // Flow collections may span lines; gather until they close
+            let mut text = text.to_string();
+            while !flow_closed(&text) {
+                let Some(line) = self.lines.get(self.pos) else {
+                    return Err(format!("line {}: unclosed flow collection", number));
+                };
+                text.push(' ');
+                text.push_str(&line.text);
+                self.pos += 1;
+            }
+            return self.flow(&text, number);
+        }
+        if text.starts_with(['"', '\'', '*']) {
+            return self.flow(text, number);
+        }
+
+        // A plain scalar may continue on more indented lines
+        let mut value = text.to_string();
+        while let Some(line) = self.peek() {
+            if line.indent <= indent || split_key(&line.text).is_some() {
+                break;
+            }
+            value.push(' ');
+            value.push_str(&line.text);
+            self.pos += 1;
+        }
+        Ok(plain_scalar(&value))
+    }
+
+    fn flow(&self, text: &str, number: usize) -> Result<Value, String> {
+        let mut flow = Flow::new(text, number);
+        flow.anchors = Some(&self.anchors);
+        let value = flow.value()?;
+        flow.skip_spaces();
+        match flow.peek() {
+            None => Ok(value),
+            Some(c) => Err(format!("line {}: unexpected '{}' after value", number, c)),
+        }
+    }
+
+    /// A `|` (literal) or `>` (folded) block scalar following the header line
+    fn block_scalar(
+        &mut self,
+        folded: bool,
+        header: &str,
+        indent: usize,
+        number: usize,
+    ) -> Result<String, String> {
+        let chomp = match header.trim() {
+            "" => None,
+            "-" => Some(false),
+            "+" => Some(true),
+            other => {
+                return Err(format!(
+                    "line {}: unsupported block scalar header '{}'",
+                    number, other
+                ));
+            }
+        };
+
+        let mut content_indent = None;
+        let mut body: Vec<&str> = Vec::new();
+        while let Some(raw) = self.raw.get(self.pos) {
+            let trimmed = raw.trim_start_matches(' ');
+            let line_indent = raw.len() - trimmed.len();
+            if trimmed.is_empty() {
+                body.push("");
+            } else {
+                let min = *content_indent.get_or_insert(line_indent);
+                if line_indent < min || line_indent <= indent {
+                    break;
+                }
+                body.push(&raw[min..]);
+            }
+            self.pos += 1;
+        }
+
+        // Trailing blank lines belong to the document unless kept
+        let trailing = body.iter().rev().take_while(|l| l.is_empty()).count();
+        let content = &body[..body.len() - trailing];
+        let mut text = if folded {
+            let mut text = String::new();
+            for (i, line) in content.iter().enumerate() {
+                if i > 0 {
+                    text.push(if line.is_empty() || content[i - 1].is_empty() {
+                        '\n'
+                    } else {
+                        ' '
+                    });
+                }
+                text.push_str(line);
+            }
+            text
+        } else {
+            content.join("\n")
+        };
+        match chomp {
+            Some(false) => {}
+            None if !text.is_empty() => text.push('\n'),
+            None => {}
+            Some(true) => text.push_str(&"\n".repeat(trailing + 1)),
+        }
+        Ok(text)
+    }
+}
+
+/// Whether the brackets of a flow collection are balanced
+fn flow_closed(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+/// Parser for flow collections and quoted scalars within one line
+struct Flow<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    number: usize,
+    anchors: Option<&'a HashMap<String, Value>>,
+}
+
+impl<'a> Flow<'a> {
+    fn new(text: &str, number: usize) -> Self {
+        Flow {
+            chars: text.chars().collect(),
+            pos: 0,
+            number,
+            anchors: None,
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("line {}: {}", self.number, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Byte offset of the current position in the original text
+    fn byte_pos(&self) -> usize {
+        self.chars[..self.pos].iter().map(|c| c.len_utf8()).sum()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_spaces();
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_spaces();
+                    if self.eat(']') {
+                        break;
+                    }
+                    items.push(self.value()?);
+                    self.skip_spaces();
+                    if !self.eat(',') && self.peek() != Some(']') {
+                        return Err(self.error("expected ',' or ']' in flow sequence"));
+                    }
+                }
+                Ok(Value::Sequence(items))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                loop {
+                    self.skip_spaces();
+                    if self.eat('}') {
+                        break;
+                    }
+                    let key = match self.peek() {
+                        Some(q @ ('"' | '\'')) => self.quoted(q)?,
+                        _ => self.plain(true),
+                    };
+                    self.skip_spaces();
+                    let value = if self.eat(':') {
+                        match self.peek() {
+                            Some(',' | '}') => Value::Null,
+                            _ => self.value()?,
+                        }
+                    } else {
+                        Value::Null
+                    };
+                    entries.push((key, value));
+                    self.skip_spaces();
+                    if !self.eat(',') && self.peek() != Some('}') {
+                        return Err(self.error("expected ',' or '}' in flow mapping"));
+                    }
+                }
+                Ok(Value::Mapping(entries))
+            }
+            Some(q @ ('"' | '\'')) => Ok(Value::String(self.quoted(q)?)),
+            Some('*') => {
+                self.pos += 1;
+                let name = self.plain(false);
+                self.anchors
+                    .and_then(|anchors| anchors.get(&name))
+                    .cloned()
+                    .ok_or_else(|| self.error(&format!("unknown alias '*{}'", name)))
+            }
+            _ => Ok(plain_scalar(&self.plain(false))),
+        }
+    }
+
+    /// A plain scalar inside a flow collection; keys also end at `:`
+    fn plain(&mut self, key: bool) -> String {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            let ends_key = key
+                && c == ':'
+                && self
+                    .chars
+                    .get(self.pos + 1)
+                    .is_none_or(|n| n.is_whitespace() || ",}".contains(*n));
+            if ",]}".contains(c) || ends_key {
+                break;
+            }
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, String> {
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match c {
+                '\'' if quote == '\'' => {
+                    if self.eat('\'') {
+                        value.push('\'');
+                    } else {
+                        return Ok(value);
+                    }
+                }
+                '"' if quote == '"' => return Ok(value),
+                '\\' if quote == '"' => value.push(self.escape()?),
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("unterminated string"));
+        };
+        self.pos += 1;
+        let hex_digits = match c {
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            _ => {
+                return Ok(match c {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    ' ' | '/' | '\\' | '"' => c,
+                    _ => return Err(self.error(&format!("invalid escape '\\{}'", c))),
+                });
+            }
+        };
+        let end = (self.pos + hex_digits).min(self.chars.len());
+        let digits: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(&format!("invalid escape '\\{}{}'", c, digits)))
+    }
+}
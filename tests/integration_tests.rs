@@ -3,7 +3,8 @@
 
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Helper to get the tickle binary path
@@ -42,6 +43,74 @@ fn cleanup_dir(path: &PathBuf) {
     }
 }
 
+/// Write an executable stub script into `<dir>/bin` so it shadows the real tool
+fn write_stub(dir: &Path, name: &str, script: &str) {
+    let bin_dir = dir.join("bin");
+    fs::create_dir_all(&bin_dir).expect("Failed to create stub bin dir");
+    let path = bin_dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}", script)).expect("Failed to write stub");
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+        .expect("Failed to make stub executable");
+}
+
+/// PATH with the stub directory of `dir` in front of the inherited PATH
+fn stub_path(dir: &Path) -> String {
+    format!(
+        "{}:{}",
+        dir.join("bin").display(),
+        env::var("PATH").unwrap_or_default()
+    )
+}
+
+/// A fake systemctl where every unit works except a few special names:
+/// "broken" always fails, "flaky" fails on its first call and "slow" hangs
+const SYSTEMCTL_STUB: &str = r#"case "$1" in
+  is-active) echo active ;;
+  show) echo "CanRestart=yes" ;;
+  restart|start|stop)
+    case "$2" in
+      broken)
+        echo "Unit broken.service not found." >&2
+        exit 5
+        ;;
+      flaky)
+        if [ ! -e "$HOME/flaky-ran" ]; then
+          touch "$HOME/flaky-ran"
+          echo "Job for flaky.service failed." >&2
+          exit 1
+        fi
+        ;;
+      slow) sleep 10 ;;
+    esac
+    sleep 0.3
+    ;;
+esac
+exit 0
+"#;
+
+/// Write a user config file under `<dir>/.config/tickle/config.toml`
+fn write_user_config(dir: &Path, contents: &str) {
+    let config_dir = dir.join(".config").join("tickle");
+    fs::create_dir_all(&config_dir).expect("Failed to create config dir");
+    fs::write(config_dir.join("config.toml"), contents).expect("Failed to write config");
+}
+
+/// Run tickle with stubbed tools and `dir` as HOME
+fn run_stubbed(dir: &Path, args: &[&str]) -> std::process::Output {
+    Command::new(get_tickle_binary())
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("COMPOSE_FILE")
+        .env_remove("COMPOSE_PROFILES")
+        .env_remove("DOCKER_CONTEXT")
+        .env_remove("DOCKER_HOST")
+        .env("PATH", stub_path(dir))
+        .output()
+        .expect("Failed to execute tickle")
+}
+
 #[test]
 fn test_tickle_help() {
     let output = Command::new(get_tickle_binary())
@@ -343,3 +412,2175 @@ fn test_multiple_help_flags() {
 
     assert!(output.status.success());
 }
+
+#[test]
+fn test_tickle_multiple_services_summary() {
+    let test_dir = create_temp_dir("multi_summary");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+
+    let output = Command::new(get_tickle_binary())
+        .args(["alpha", "broken", "gamma"])
+        .env("HOME", &test_dir)
+        .env("PATH", stub_path(&test_dir))
+        .output()
+        .expect("Failed to execute tickle");
+
+    // One failed service makes the whole run fail, but the others still run
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Summary"));
+    assert!(stdout.contains("Successfully restarted alpha"));
+    assert!(stdout.contains("Successfully restarted gamma"));
+    assert!(stdout.contains("succeeded: 2, failed: 1"));
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| tickle | alpha | SUCCESS"));
+    assert!(history.contains("| tickle | broken | FAILED"));
+    assert!(history.contains("| tickle | gamma | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_parallel_output_not_interleaved() {
+    let test_dir = create_temp_dir("parallel_blocks");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+
+    let output = Command::new(get_tickle_binary())
+        .args(["--parallel", "3", "alpha", "beta", "gamma"])
+        .env("HOME", &test_dir)
+        .env("PATH", stub_path(&test_dir))
+        .output()
+        .expect("Failed to execute tickle");
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("succeeded: 3, failed: 0"));
+
+    // Every target's lines must follow its own header without interruption
+    for name in ["alpha", "beta", "gamma"] {
+        let header = format!("── {} ──", name);
+        let block: Vec<&str> = stdout
+            .lines()
+            .skip_while(|line| *line != header)
+            .skip(1)
+            .take_while(|line| !line.is_empty())
+            .collect();
+        assert!(!block.is_empty(), "missing block for {}", name);
+        assert!(block.iter().all(|line| !line.contains("──")));
+        assert!(block.iter().any(|line| line.contains(name)));
+    }
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_parallel_invalid_value() {
+    let output = Command::new(get_tickle_binary())
+        .args(["--parallel", "0", "nginx"])
+        .output()
+        .expect("Failed to execute tickle");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--parallel"));
+}
+
+#[test]
+fn test_tickle_follow_rejects_multiple_services() {
+    let output = Command::new(get_tickle_binary())
+        .args(["-f", "nginx", "varnish"])
+        .output()
+        .expect("Failed to execute tickle");
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("single service"));
+}
+
+#[test]
+fn test_tickle_config_strategy_and_cli_override() {
+    let test_dir = create_temp_dir("config_strategy");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(
+        &test_dir,
+        "[services.\"php8.2-fpm\"]\nstrategy = \"stop-start\"\n",
+    );
+
+    let output = run_stubbed(&test_dir, &["php8.2-fpm.service"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Using strategy: StopStart"));
+
+    // The command line wins over the config file
+    let output = run_stubbed(&test_dir, &["--strategy", "restart", "php8.2-fpm"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Using strategy: Restart"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_config_protected_requires_force() {
+    let test_dir = create_temp_dir("config_protected");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(&test_dir, "[services.postgresql]\nprotected = true\n");
+
+    let output = run_stubbed(&test_dir, &["stop", "postgresql"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("protected"));
+    assert!(stderr.contains("--force"));
+
+    let output = run_stubbed(&test_dir, &["stop", "--force", "postgresql"]);
+    assert!(output.status.success());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_config_retries() {
+    let test_dir = create_temp_dir("config_retries");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(&test_dir, "[defaults]\nretries = 1\n");
+
+    let output = run_stubbed(&test_dir, &["flaky"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Attempt 1/2 failed"));
+    assert!(stdout.contains("Retrying flaky"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_health_check_failure() {
+    let test_dir = create_temp_dir("health_check");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(
+        &test_dir,
+        "[services.nginx]\nhealth_check = \"echo upstream down >&2; exit 1\"\n",
+    );
+
+    let output = run_stubbed(&test_dir, &["nginx"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Health check failed: upstream down"));
+
+    // Overriding the check on the command line makes it pass
+    let output = run_stubbed(&test_dir, &["--health-check", "true", "nginx"]);
+    assert!(output.status.success());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_health_check_leaving_background_process() {
+    let test_dir = create_temp_dir("health_check_background");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+
+    // The sleep keeps the check's output pipes open after the shell exits
+    let started = std::time::Instant::now();
+    let output = run_stubbed(
+        &test_dir,
+        &["--health-check", "echo checked; sleep 30 &", "nginx"],
+    );
+    assert!(output.status.success());
+    assert!(started.elapsed().as_secs() < 10);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("checked"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_timeout() {
+    let test_dir = create_temp_dir("timeout");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+
+    let output = run_stubbed(&test_dir, &["--timeout", "1", "slow"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("timed out after 1s"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_invalid_config() {
+    let test_dir = create_temp_dir("invalid_config");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(&test_dir, "[services.nginx]\ntimeout = \"soon\"\n");
+
+    let output = run_stubbed(&test_dir, &["nginx"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("config.toml"));
+    assert!(stderr.contains("services.nginx.timeout"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_group_expands_members() {
+    let test_dir = create_temp_dir("group_expand");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(
+        &test_dir,
+        "[groups]\nweb = [\n  \"nginx\",\n  \"php8.2-fpm\",\n  \"varnish\",\n]\n",
+    );
+
+    let output = run_stubbed(&test_dir, &["stop", "@web"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Successfully stopped nginx"));
+    assert!(stdout.contains("Successfully stopped php8.2-fpm"));
+    assert!(stdout.contains("Successfully stopped varnish"));
+    assert!(stdout.contains("succeeded: 3, failed: 0"));
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| stop | @web:nginx | SUCCESS"));
+    assert!(history.contains("| stop | @web:php8.2-fpm | SUCCESS"));
+    assert!(history.contains("| stop | @web:varnish | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_group_records_member_failures() {
+    let test_dir = create_temp_dir("group_failure");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(&test_dir, "[groups]\nweb = [\"nginx\", \"broken\"]\n");
+
+    let output = run_stubbed(&test_dir, &["@web"]);
+    assert!(!output.status.success());
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| tickle | @web:nginx | SUCCESS"));
+    assert!(history.contains("| tickle | @web:broken | FAILED"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_unknown_group() {
+    let test_dir = create_temp_dir("group_unknown");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_user_config(&test_dir, "[groups]\nweb = [\"nginx\"]\n");
+
+    let output = run_stubbed(&test_dir, &["@db"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown group @db"));
+    assert!(stderr.contains("@web"));
+
+    cleanup_dir(&test_dir);
+}
+
+/// A fake docker that records every invocation in `$HOME/calls.log`
+const DOCKER_STUB: &str = r#"case "$*" in
+  *" ps "*|"compose version") exit 0 ;;
+esac
+echo "docker $*" >> "$HOME/calls.log"
+exit 0
+"#;
+
+/// A project file mixing a compose stack, a systemd unit and a command
+const PROJECT_FILE: &str = r#"
+[[step]]
+compose = true
+strategy = "down-up"
+
+[[step]]
+unit = "myapp-worker"
+
+[[step]]
+name = "warm cache"
+command = "echo warm-cache >> \"$HOME/calls.log\""
+"#;
+
+#[test]
+fn test_tickle_project_file_runs_steps_in_order() {
+    let test_dir = create_temp_dir("project_order");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    write_stub(
+        &test_dir,
+        "systemctl",
+        &format!(
+            "case \"$1\" in restart|start|stop) echo \"systemctl $*\" >> \"$HOME/calls.log\" ;; esac\n{}",
+            SYSTEMCTL_STUB
+        ),
+    );
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+    fs::write(test_dir.join(".tickle.toml"), PROJECT_FILE).unwrap();
+
+    let output = run_stubbed(&test_dir, &[]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("step 3/3: warm cache"));
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker compose -f docker-compose.yml down",
+            "docker compose -f docker-compose.yml up -d",
+            "systemctl restart myapp-worker",
+            "warm-cache",
+        ]
+    );
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains(":myapp-worker | SUCCESS"));
+    assert!(history.contains(":warm cache | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_project_file_stop_reverses_order() {
+    let test_dir = create_temp_dir("project_stop");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    write_stub(
+        &test_dir,
+        "systemctl",
+        &format!(
+            "case \"$1\" in restart|start|stop) echo \"systemctl $*\" >> \"$HOME/calls.log\" ;; esac\n{}",
+            SYSTEMCTL_STUB
+        ),
+    );
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+    fs::write(test_dir.join(".tickle.toml"), PROJECT_FILE).unwrap();
+
+    let output = run_stubbed(&test_dir, &["stop"]);
+    assert!(output.status.success());
+
+    // The command step only runs for tickle/start by default
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "systemctl stop myapp-worker",
+            "docker compose -f docker-compose.yml down",
+        ]
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_project_file_in_parent_directory() {
+    let test_dir = create_temp_dir("project_parent");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    let project = test_dir.join("shop");
+    let nested = project.join("src");
+    fs::create_dir_all(&nested).unwrap();
+    fs::create_dir_all(project.join("deploy")).unwrap();
+    fs::write(project.join("deploy/compose.yml"), "services: {}\n").unwrap();
+    fs::write(
+        project.join(".tickle.toml"),
+        "[[step]]\ncompose = \"deploy/compose.yml\"\n\n\
+         [[step]]\nname = \"migrate\"\ncommand = \"echo migrated; pwd >> \\\"$HOME/calls.log\\\"\"\n",
+    )
+    .unwrap();
+
+    let run = |args: &[&str]| {
+        Command::new(get_tickle_binary())
+            .args(args)
+            .current_dir(&nested)
+            .env("HOME", &test_dir)
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("COMPOSE_FILE")
+            .env("PATH", stub_path(&test_dir))
+            .output()
+            .expect("Failed to execute tickle")
+    };
+
+    // Command steps go through the same runner as the other steps
+    let output = run(&["--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would run: sh -c 'echo migrated;"));
+    assert!(!test_dir.join("calls.log").exists());
+
+    let output = run(&[]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("sh │ migrated"));
+
+    let project = project.canonicalize().unwrap();
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker compose -f compose.yml up -d --force-recreate".to_string(),
+            project.display().to_string(),
+        ]
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_project_file_invalid_step() {
+    let test_dir = create_temp_dir("project_invalid");
+    fs::write(
+        test_dir.join(".tickle.toml"),
+        "[[step]]\nunit = \"a\"\ncommand = \"b\"\n",
+    )
+    .unwrap();
+
+    let output = run_stubbed(&test_dir, &[]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(".tickle.toml"));
+    assert!(stderr.contains("step 1"));
+
+    cleanup_dir(&test_dir);
+}
+
+/// Config defining a pm2-like custom target type backed by a stub script
+const PM2_CONFIG: &str = r#"
+[types.pm2]
+start = "pm2 start {name}"
+stop = "pm2 stop {name}"
+restart = "pm2 restart {name}"
+status = "pm2 describe {name}"
+
+[types.script]
+start = "echo start {name} >> \"$HOME/calls.log\""
+stop = "echo stop {name} >> \"$HOME/calls.log\""
+"#;
+
+const PM2_STUB: &str = r#"echo "pm2 $*" >> "$HOME/calls.log"
+exit 0
+"#;
+
+#[test]
+fn test_tickle_custom_target_type() {
+    let test_dir = create_temp_dir("custom_type");
+    write_stub(&test_dir, "pm2", PM2_STUB);
+    write_user_config(&test_dir, PM2_CONFIG);
+
+    let output = run_stubbed(&test_dir, &["pm2:api"]);
+    assert!(output.status.success());
+    let output = run_stubbed(&test_dir, &["stop", "pm2:api"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Final state: Active"));
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert!(calls.contains("pm2 restart api"));
+    assert!(calls.contains("pm2 stop api"));
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| tickle | pm2:api | SUCCESS"));
+    assert!(history.contains("| stop | pm2:api | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_custom_target_show_logs_rejected_before_operation() {
+    let test_dir = create_temp_dir("custom_show_logs");
+    write_stub(&test_dir, "pm2", PM2_STUB);
+    write_user_config(
+        &test_dir,
+        &PM2_CONFIG.replace(
+            "[types.script]",
+            "logs = \"pm2 logs {name}\"\n\n[types.script]",
+        ),
+    );
+
+    for args in [
+        &["--show-logs", "pm2:api"][..],
+        &["start", "--show-logs", "pm2:api"],
+    ] {
+        let output = run_stubbed(&test_dir, args);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("can't show only the logs since the operation"));
+    }
+    assert!(!test_dir.join("calls.log").exists());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_custom_target_logs_only_follow() {
+    let test_dir = create_temp_dir("custom_logs_follow");
+    write_stub(&test_dir, "pm2", PM2_STUB);
+    write_user_config(
+        &test_dir,
+        &PM2_CONFIG.replace(
+            "[types.script]",
+            "logs = \"pm2 logs {name}\"\n\n[types.script]",
+        ),
+    );
+
+    let output = run_stubbed(&test_dir, &["logs", "--no-follow", "pm2:api"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("can only follow its logs"));
+
+    let output = run_stubbed(&test_dir, &["logs", "--dry-run", "pm2:api"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would run: sh -c 'pm2 logs "));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_custom_target_without_restart_uses_stop_start() {
+    let test_dir = create_temp_dir("custom_stop_start");
+    write_user_config(&test_dir, PM2_CONFIG);
+
+    let output = run_stubbed(&test_dir, &["script:worker"]);
+    assert!(output.status.success());
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(calls, ["stop worker", "start worker"]);
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_dry_run_changes_nothing() {
+    let test_dir = create_temp_dir("dry_run");
+    write_stub(&test_dir, "pm2", PM2_STUB);
+    write_stub(
+        &test_dir,
+        "systemctl",
+        &format!(
+            "case \"$1\" in restart|start|stop) echo \"systemctl $*\" >> \"$HOME/calls.log\" ;; esac\n{}",
+            SYSTEMCTL_STUB
+        ),
+    );
+    write_user_config(&test_dir, PM2_CONFIG);
+
+    let output = run_stubbed(&test_dir, &["--dry-run", "nginx", "pm2:api"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would run: systemctl restart nginx"));
+    assert!(stdout.contains("Would run: sh -c 'pm2 restart '\\''api'\\'''"));
+    assert!(stdout.contains("no changes were made"));
+
+    // Only read-only status queries may have run, and nothing is recorded
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap_or_default();
+    assert!(!calls.contains("restart"));
+    assert!(!test_dir.join(".tickle/history.log").exists());
+
+    cleanup_dir(&test_dir);
+}
+
+/// A docker stub whose compose file defines the `web` and `worker` services
+const COMPOSE_SERVICES_STUB: &str = r#"case "$*" in
+  *"config --services"*) printf 'web\nworker\n'; exit 0 ;;
+  "compose version") exit 0 ;;
+  *" ps "*|"compose version") exit 0 ;;
+esac
+echo "docker $*" >> "$HOME/calls.log"
+exit 0
+"#;
+
+#[test]
+fn test_tickle_compose_service_by_name() {
+    let test_dir = create_temp_dir("compose_service");
+    write_stub(&test_dir, "docker", COMPOSE_SERVICES_STUB);
+    write_stub(
+        &test_dir,
+        "systemctl",
+        &format!(
+            "case \"$1\" in restart|start|stop) echo \"systemctl $*\" >> \"$HOME/calls.log\" ;; esac\n{}",
+            SYSTEMCTL_STUB
+        ),
+    );
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    assert!(run_stubbed(&test_dir, &["web", "nginx"]).status.success());
+    assert!(run_stubbed(&test_dir, &["stop", "worker"]).status.success());
+    assert!(
+        run_stubbed(&test_dir, &["--systemd", "web"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker compose -f docker-compose.yml restart web",
+            "systemctl restart nginx",
+            "docker compose -f docker-compose.yml stop worker",
+            "systemctl restart web",
+        ]
+    );
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("/docker-compose.yml:web | SUCCESS"));
+    assert!(history.contains("| tickle | nginx | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_finds_compose_file_in_parent_directory() {
+    let test_dir = create_temp_dir("compose_parent");
+    write_stub(
+        &test_dir,
+        "docker",
+        &format!(
+            "echo \"cwd $(pwd)\" >> \"$HOME/calls.log\"\n{}",
+            DOCKER_STUB
+        ),
+    );
+    let project = test_dir.join("app");
+    let nested = project.join("src").join("deep");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(project.join("compose.yml"), "services: {}\n").unwrap();
+
+    let output = Command::new(get_tickle_binary())
+        .arg("start")
+        .current_dir(&nested)
+        .env("HOME", &test_dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env("PATH", stub_path(&test_dir))
+        .output()
+        .expect("Failed to execute tickle");
+    assert!(output.status.success());
+
+    let compose_path = project.canonicalize().unwrap().join("compose.yml");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&compose_path.display().to_string()));
+
+    // Compose runs from the project directory, not the CWD
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert!(calls.contains(&format!(
+        "cwd {}",
+        project.canonicalize().unwrap().display()
+    )));
+    assert!(calls.contains("docker compose -f compose.yml up -d"));
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains(&format!(
+        "| start | compose:{} | SUCCESS",
+        compose_path.display()
+    )));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_search_stops_at_home() {
+    let test_dir = create_temp_dir("compose_home_boundary");
+    let home = test_dir.join("home");
+    let nested = home.join("work");
+    fs::create_dir_all(&nested).unwrap();
+    // Above $HOME, so it must not be picked up
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    let output = Command::new(get_tickle_binary())
+        .current_dir(&nested)
+        .env("HOME", &home)
+        .env_remove("XDG_CONFIG_HOME")
+        .output()
+        .expect("Failed to execute tickle");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no compose file found"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_options_passed_to_every_invocation() {
+    let test_dir = create_temp_dir("compose_options");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    fs::write(test_dir.join("compose.yml"), "services: {}\n").unwrap();
+    fs::write(test_dir.join("compose.override.yml"), "services: {}\n").unwrap();
+    fs::write(test_dir.join("extra.yml"), "services: {}\n").unwrap();
+    fs::write(test_dir.join("prod.env"), "TAG=1\n").unwrap();
+
+    let output = Command::new(get_tickle_binary())
+        .args([
+            "--profile",
+            "debug",
+            "-p",
+            "shop",
+            "--env-file",
+            "prod.env",
+            "--file",
+            "extra.yml",
+            "--compose-strategy",
+            "down-up",
+            "--dry-run",
+            "-f",
+        ])
+        .current_dir(&test_dir)
+        .env("HOME", &test_dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("COMPOSE_FILE")
+        .env("COMPOSE_PROFILES", "web,debug")
+        .env("PATH", stub_path(&test_dir))
+        .output()
+        .expect("Failed to execute tickle");
+    assert!(output.status.success());
+
+    let global = "-f compose.yml -f compose.override.yml -f extra.yml -p shop \
+                  --profile web --profile debug --env-file prod.env";
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("Would run: docker compose {} down", global)));
+    assert!(stdout.contains(&format!("Would run: docker compose {} up -d", global)));
+    assert!(stdout.contains(&format!("Would run: docker compose {} logs -f", global)));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_file_env() {
+    let test_dir = create_temp_dir("compose_file_env");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    let deploy = test_dir.join("deploy");
+    fs::create_dir_all(&deploy).unwrap();
+    fs::write(deploy.join("base.yml"), "services: {}\n").unwrap();
+    fs::write(deploy.join("prod.yml"), "services: {}\n").unwrap();
+
+    for dir in ["app", "shop"] {
+        fs::create_dir_all(test_dir.join("srv").join(dir)).unwrap();
+        fs::write(
+            test_dir.join("srv").join(dir).join("compose.yml"),
+            "services: {}\n",
+        )
+        .unwrap();
+    }
+    let run = |args: &[&str]| {
+        Command::new(get_tickle_binary())
+            .args(args)
+            .current_dir(&test_dir)
+            .env("HOME", &test_dir)
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("COMPOSE_PROFILES")
+            .env("COMPOSE_FILE", "deploy/base.yml:deploy/prod.yml")
+            .env("PATH", stub_path(&test_dir))
+            .output()
+            .expect("Failed to execute tickle")
+    };
+    assert!(run(&["stop"]).status.success());
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let base = test_dir.canonicalize().unwrap().join("deploy");
+    assert_eq!(
+        calls.trim(),
+        format!(
+            "docker compose -f {}/base.yml -f {}/prod.yml down",
+            base.display(),
+            base.display()
+        )
+    );
+
+    // It names the stack found here, not registered stacks or -C directories
+    fs::remove_file(test_dir.join("calls.log")).unwrap();
+    assert!(run(&["stack", "add", "app", "srv/app"]).status.success());
+    assert!(run(&["stop", "stack:app"]).status.success());
+    assert!(
+        run(&["stop", "-C", "srv/app", "-C", "srv/shop"])
+            .status
+            .success()
+    );
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(calls, ["docker compose -f compose.yml down"; 3]);
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_strategies() {
+    let test_dir = create_temp_dir("compose_strategies");
+    write_stub(&test_dir, "docker", COMPOSE_SERVICES_STUB);
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    assert!(run_stubbed(&test_dir, &[]).status.success());
+    for strategy in ["restart", "build", "down-up"] {
+        let output = run_stubbed(&test_dir, &["--compose-strategy", strategy]);
+        assert!(output.status.success());
+    }
+    assert!(
+        run_stubbed(&test_dir, &["--compose-strategy", "recreate", "web"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker compose -f docker-compose.yml up -d --force-recreate",
+            "docker compose -f docker-compose.yml restart",
+            "docker compose -f docker-compose.yml up -d --build",
+            "docker compose -f docker-compose.yml down",
+            "docker compose -f docker-compose.yml up -d",
+            "docker compose -f docker-compose.yml up -d --force-recreate web",
+        ]
+    );
+
+    let output = run_stubbed(&test_dir, &["--compose-strategy", "blue-green"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown compose strategy"));
+
+    cleanup_dir(&test_dir);
+}
+
+/// A docker stub with one image whose ID changes when it is pulled
+const PULL_STUB: &str = r#"case "$*" in
+  *"config --images"*) [ -f "$HOME/no-images" ] && exit 1; echo "nginx:latest"; exit 0 ;;
+  "image inspect"*) cat "$HOME/image-id"; exit 0 ;;
+  *" ps "*|"compose version") exit 0 ;;
+  *" pull"*) echo "sha256:new" > "$HOME/image-id" ;;
+esac
+echo "docker $*" >> "$HOME/calls.log"
+exit 0
+"#;
+
+#[test]
+fn test_tickle_compose_pull_before_restart() {
+    let test_dir = create_temp_dir("compose_pull");
+    write_stub(&test_dir, "docker", PULL_STUB);
+    fs::write(test_dir.join("image-id"), "sha256:old\n").unwrap();
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["--pull"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Updated images: nginx:latest"));
+    assert!(
+        run_stubbed(&test_dir, &["--pull", "--compose-strategy", "build"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker compose -f docker-compose.yml pull",
+            "docker compose -f docker-compose.yml up -d --force-recreate",
+            "docker compose -f docker-compose.yml pull",
+            "docker compose -f docker-compose.yml build",
+            "docker compose -f docker-compose.yml up -d --build",
+        ]
+    );
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    let history: Vec<&str> = history.lines().collect();
+    assert!(history[0].ends_with("| SUCCESS | images updated: nginx:latest"));
+    assert!(history[1].ends_with("| SUCCESS | images unchanged"));
+
+    fs::write(test_dir.join("no-images"), "").unwrap();
+    assert!(run_stubbed(&test_dir, &["--pull"]).status.success());
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.trim_end().ends_with("| SUCCESS | images: unknown"));
+
+    let output = run_stubbed(&test_dir, &["start", "--pull"]);
+    assert!(!output.status.success());
+
+    cleanup_dir(&test_dir);
+}
+
+/// A docker stub whose `compose ps` prints the contents of `$HOME/ps.txt`
+const PS_STUB: &str = r#"case "$*" in
+  *" ps "*) cat "$HOME/ps.txt"; exit 0 ;;
+esac
+exit 0
+"#;
+
+#[test]
+fn test_tickle_compose_waits_for_healthy_containers() {
+    let test_dir = create_temp_dir("compose_wait");
+    write_stub(&test_dir, "docker", PS_STUB);
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    fs::write(
+        test_dir.join("ps.txt"),
+        "app-web-1\tweb\trunning\thealthy\napp-cron-1\tcron\trunning\t\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ app-web-1 (web): running, healthy"));
+    assert!(stdout.contains("✅ app-cron-1 (cron): running"));
+
+    fs::write(
+        test_dir.join("ps.txt"),
+        "app-web-1\tweb\trunning\tunhealthy\napp-cron-1\tcron\texited\t\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &[]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stdout.contains("❌ app-cron-1 (cron): exited"));
+    assert!(stderr.contains("2 container(s) unhealthy or exited"));
+
+    fs::write(
+        test_dir.join("ps.txt"),
+        "app-web-1\tweb\trunning\tstarting\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["start", "--wait-timeout", "1"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not ready after 1s"));
+    assert!(
+        run_stubbed(&test_dir, &["start", "--no-wait"])
+            .status
+            .success()
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_one_shot_services() {
+    let test_dir = create_temp_dir("compose_one_shot");
+    write_stub(&test_dir, "docker", PS_STUB);
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    // name, service, state, health, status, ports, exit code
+    fs::write(
+        test_dir.join("ps.txt"),
+        "app-web-1\tweb\trunning\t\tUp 3 seconds\t\t0\n\
+         app-migrate-1\tmigrate\texited\t\tExited (0) 1 second ago\t\t0\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("✅ app-migrate-1 (migrate): exited (0)"));
+
+    fs::write(
+        test_dir.join("ps.txt"),
+        "app-web-1\tweb\trunning\t\tUp 3 seconds\t\t0\n\
+         app-migrate-1\tmigrate\texited\t\tExited (1) 1 second ago\t\t1\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("❌ app-migrate-1 (migrate): exited (1)"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_failure_not_retried_on_other_cli() {
+    let test_dir = create_temp_dir("compose_cli_failure");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  "compose version") exit 0 ;;
+  *" up "*) echo "yaml: line 3: mapping values are not allowed" >&2; exit 1 ;;
+esac
+exit 0
+"#,
+    );
+    write_stub(
+        &test_dir,
+        "docker-compose",
+        "echo \"docker-compose $*\" >> \"$HOME/calls.log\"\n",
+    );
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("docker compose up failed: yaml: line 3"));
+    assert!(!test_dir.join("calls.log").exists());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_uses_podman_when_docker_compose_missing() {
+    let test_dir = create_temp_dir("compose_podman");
+    write_stub(&test_dir, "docker", "exit 1\n");
+    write_stub(&test_dir, "docker-compose", "exit 127\n");
+    write_stub(
+        &test_dir,
+        "podman",
+        r#"case "$*" in
+  "compose version"|*" ps "*) exit 0 ;;
+esac
+echo "podman $*" >> "$HOME/calls.log"
+"#,
+    );
+    fs::write(test_dir.join("compose.yaml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["stop"]);
+    assert!(output.status.success());
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert_eq!(calls.trim(), "podman compose -f compose.yaml down");
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_streams_command_output() {
+    let test_dir = create_temp_dir("stream_output");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  "compose version"|*" ps "*) exit 0 ;;
+  *" up "*) echo "Container app-web-1  Started"; exit 0 ;;
+  *" down"*) echo "pulling..."; echo "Error: network app_default in use" >&2; exit 1 ;;
+esac
+"#,
+    );
+    fs::write(test_dir.join("docker-compose.yml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("   docker │ Container app-web-1  Started"));
+
+    let output = run_stubbed(&test_dir, &["start", "--quiet"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("app-web-1"));
+
+    let output = run_stubbed(&test_dir, &["stop"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("   docker │ Error: network app_default in use"));
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(
+        history
+            .contains("| FAILED | docker compose down failed: Error: network app_default in use")
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+/// A podman stub whose containers are all running and with no compose support
+const PODMAN_STUB: &str = r#"case "$1 $2" in
+  "container inspect") echo "running "; exit 0 ;;
+  compose*) exit 1 ;;
+esac
+echo "podman $*" >> "$HOME/calls.log"
+"#;
+
+#[test]
+fn test_tickle_podman_container_target() {
+    let test_dir = create_temp_dir("podman_container");
+    write_stub(&test_dir, "podman", PODMAN_STUB);
+
+    let output = run_stubbed(&test_dir, &["podman:redis"]);
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("Current state of podman:redis: running")
+    );
+    assert!(
+        run_stubbed(&test_dir, &["stop", "podman:redis"])
+            .status
+            .success()
+    );
+    assert!(
+        run_stubbed(&test_dir, &["-s", "podman:redis"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "podman restart redis",
+            "podman stop redis",
+            "podman stop redis",
+            "podman start redis",
+        ]
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_quadlet_unit_notice() {
+    let test_dir = create_temp_dir("quadlet");
+    write_stub(
+        &test_dir,
+        "systemctl",
+        &format!(
+            "case \"$*\" in *SourcePath*) echo \"SourcePath=/etc/containers/systemd/$2\" | sed 's/-pod.service$/.pod/; s/.service$/.container/'; exit 0 ;; esac\n\
+             case \"$1\" in restart|start|stop) echo \"systemctl $*\" >> \"$HOME/calls.log\" ;; esac\n{}",
+            SYSTEMCTL_STUB
+        ),
+    );
+
+    let output = run_stubbed(&test_dir, &["web.container"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(
+        "web.service is generated by Quadlet from /etc/containers/systemd/web.container"
+    ));
+    assert!(
+        run_stubbed(&test_dir, &["start", "db.pod"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "systemctl restart web.service",
+            "systemctl start db-pod.service"
+        ]
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_uses_podman_compose() {
+    let test_dir = create_temp_dir("podman_compose");
+    write_stub(&test_dir, "docker", "exit 1\n");
+    write_stub(&test_dir, "docker-compose", "exit 127\n");
+    write_stub(&test_dir, "podman", PODMAN_STUB);
+    write_stub(
+        &test_dir,
+        "podman-compose",
+        "case \"$*\" in version|*\" ps \"*) exit 0 ;; esac\necho \"podman-compose $*\" >> \"$HOME/calls.log\"\n",
+    );
+    fs::write(test_dir.join("compose.yaml"), "services: {}\n").unwrap();
+
+    assert!(run_stubbed(&test_dir, &["start"]).status.success());
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert_eq!(calls.trim(), "podman-compose -f compose.yaml up -d");
+    let output = run_stubbed(&test_dir, &["--dry-run"]);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("(podman-compose up -d --force-recreate)")
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_waits_without_ps_format() {
+    let test_dir = create_temp_dir("podman_compose_wait");
+    write_stub(&test_dir, "docker", "exit 1\n");
+    write_stub(&test_dir, "docker-compose", "exit 127\n");
+    write_stub(
+        &test_dir,
+        "podman",
+        r#"[ "$1 $2" = "container inspect" ] || exit 1
+[ "$5" = 3f2a ] || exit 1
+printf '/app_web_1\tweb\t%s\t\t\t\t%s\n' $(cat "$HOME/web-state")
+"#,
+    );
+    write_stub(
+        &test_dir,
+        "podman-compose",
+        r#"case "$*" in
+  *--format*) echo "unknown flag: --format" >&2; exit 1 ;;
+  *" ps -q") echo 3f2a ;;
+esac
+"#,
+    );
+    fs::write(test_dir.join("compose.yaml"), "services: {}\n").unwrap();
+
+    fs::write(test_dir.join("web-state"), "running 0\n").unwrap();
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("✅ app_web_1 (web): running"));
+
+    fs::write(test_dir.join("web-state"), "exited 2\n").unwrap();
+    let output = run_stubbed(&test_dir, &["start"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("❌ app_web_1 (web): exited (2)"));
+
+    cleanup_dir(&test_dir);
+}
+
+/// A docker stub with one container, `redis`, whose state is kept in
+/// `$HOME/redis-state`
+const DOCKER_CONTAINER_STUB: &str = r#"case "$1 $2" in
+  "container inspect")
+    [ "$3" = "--format" ] || { [ "$3" = redis ] && exit 0 || exit 1; }
+    [ "$5" = redis ] || { echo "Error: No such container: $5" >&2; exit 1; }
+    echo "$(cat "$HOME/redis-state") healthy"; exit 0 ;;
+  "compose version") exit 1 ;;
+esac
+echo "docker $*" >> "$HOME/calls.log"
+case "$1" in
+  stop) echo exited > "$HOME/redis-state" ;;
+  start|restart) echo running > "$HOME/redis-state" ;;
+esac
+"#;
+
+#[test]
+fn test_tickle_docker_container_target() {
+    let test_dir = create_temp_dir("docker_container");
+    write_stub(&test_dir, "docker", DOCKER_CONTAINER_STUB);
+    fs::write(test_dir.join("redis-state"), "running\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["stop", "container:redis"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Current state of container:redis: running (healthy)"));
+    assert!(stdout.contains("New state of container:redis: exited (healthy)"));
+    assert!(
+        run_stubbed(&test_dir, &["start", "container:redis"])
+            .status
+            .success()
+    );
+
+    let output = run_stubbed(&test_dir, &["--dry-run", "-f", "container:redis"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Would run: docker logs -f redis"));
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(calls, ["docker stop redis", "docker start redis"]);
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_container_detected_when_no_unit_matches() {
+    let test_dir = create_temp_dir("docker_container_detect");
+    write_stub(&test_dir, "docker", DOCKER_CONTAINER_STUB);
+    write_stub(
+        &test_dir,
+        "systemctl",
+        &format!(
+            "case \"$1 $2\" in \"cat redis\") exit 1 ;; esac\n\
+             case \"$1\" in restart|start|stop) echo \"systemctl $*\" >> \"$HOME/calls.log\" ;; esac\n{}",
+            SYSTEMCTL_STUB
+        ),
+    );
+    fs::write(test_dir.join("redis-state"), "running\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["redis", "nginx"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No systemd unit named redis"));
+    assert!(
+        run_stubbed(&test_dir, &["--systemd", "redis"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker restart redis",
+            "systemctl restart nginx",
+            "systemctl stop redis",
+            "systemctl start redis"
+        ]
+    );
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| tickle | container:redis | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_registered_stack() {
+    let test_dir = create_temp_dir("stack_registry");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  *" ps "*|"compose version") exit 0 ;;
+esac
+echo "$PWD: docker $*" >> "$HOME/calls.log"
+exit 0
+"#,
+    );
+    let app_dir = test_dir.join("srv").join("myapp");
+    fs::create_dir_all(&app_dir).unwrap();
+    fs::write(app_dir.join("compose.yaml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["stack", "add", "myapp", "srv/myapp"]);
+    assert!(output.status.success());
+    let output = run_stubbed(&test_dir, &["stack", "list"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("myapp"));
+    assert!(stdout.contains("srv/myapp/compose.yaml"));
+
+    assert!(run_stubbed(&test_dir, &["stack:myapp"]).status.success());
+    assert!(
+        run_stubbed(&test_dir, &["stop", "stack:myapp"])
+            .status
+            .success()
+    );
+    let output = run_stubbed(&test_dir, &["stack:other"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Unknown stack 'other'"));
+
+    let app_dir = app_dir.canonicalize().unwrap();
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            format!(
+                "{}: docker compose -f compose.yaml up -d --force-recreate",
+                app_dir.display()
+            ),
+            format!("{}: docker compose -f compose.yaml down", app_dir.display()),
+        ]
+    );
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| tickle | stack:myapp | SUCCESS"));
+
+    assert!(
+        run_stubbed(&test_dir, &["stack", "remove", "myapp"])
+            .status
+            .success()
+    );
+    let output = run_stubbed(&test_dir, &["stack", "list"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No stacks registered"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_stack_name_with_dot_round_trips() {
+    let test_dir = create_temp_dir("stack_dotted_name");
+    let app_dir = test_dir.join("srv").join("my.app");
+    fs::create_dir_all(&app_dir).unwrap();
+    fs::write(app_dir.join("compose.yaml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["stack", "add", "my.app", "srv/my.app"]);
+    assert!(output.status.success());
+    let output = run_stubbed(&test_dir, &["stack", "list"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.lines().any(|l| l.starts_with("my.app ")));
+
+    assert!(
+        run_stubbed(&test_dir, &["stack", "remove", "my.app"])
+            .status
+            .success()
+    );
+    let output = run_stubbed(&test_dir, &["stack", "list"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("No stacks registered"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_change_directory() {
+    let test_dir = create_temp_dir("change_directory");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    let app_dir = test_dir.join("srv").join("app");
+    let shop_dir = test_dir.join("srv").join("shop");
+    fs::create_dir_all(&app_dir).unwrap();
+    fs::create_dir_all(&shop_dir).unwrap();
+    fs::write(app_dir.join("compose.yml"), "services: {}\n").unwrap();
+    fs::write(shop_dir.join("compose.yml"), "services: {}\n").unwrap();
+    fs::write(shop_dir.join(".tickle.toml"), PROJECT_FILE).unwrap();
+
+    assert!(
+        run_stubbed(&test_dir, &["-C", "srv/app", "stop"])
+            .status
+            .success()
+    );
+    assert!(run_stubbed(&test_dir, &["-C", "srv/shop"]).status.success());
+    let output = run_stubbed(&test_dir, &["-C", "srv/missing"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cannot change to directory"));
+    // -C takes one directory, even when the next argument names one too
+    assert!(
+        run_stubbed(&test_dir, &["-C", "srv/app", "--systemd", "srv"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert!(calls.starts_with("docker compose -f compose.yml down\n"));
+    let app_dir = app_dir.canonicalize().unwrap();
+    let shop_dir = shop_dir.canonicalize().unwrap();
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains(&format!(
+        "| stop | compose:{}/compose.yml | SUCCESS",
+        app_dir.display()
+    )));
+    assert!(history.contains(&format!(
+        "| tickle | project:{}:myapp-worker | SUCCESS",
+        shop_dir.display()
+    )));
+    assert!(history.contains("| tickle | srv | SUCCESS"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_several_project_directories() {
+    let test_dir = create_temp_dir("several_projects");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  *" ps "*|"compose version") exit 0 ;;
+esac
+echo "$PWD: docker $*" >> "$HOME/calls.log"
+exit 0
+"#,
+    );
+    let stacks = test_dir.join("stacks");
+    for name in ["api", "web", "empty"] {
+        fs::create_dir_all(stacks.join(name)).unwrap();
+    }
+    fs::write(stacks.join("api/compose.yml"), "services: {}\n").unwrap();
+    fs::write(stacks.join("web/compose.yml"), "services: {}\n").unwrap();
+
+    let output = run_stubbed(
+        &test_dir,
+        &[
+            "stop",
+            "-C",
+            "stacks/api",
+            "-C",
+            "stacks/web",
+            "-C",
+            "stacks/empty",
+            "--parallel",
+            "2",
+        ],
+    );
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Summary"));
+    assert!(stdout.contains("Total: 3, succeeded: 2, failed: 1"));
+
+    let stacks = stacks.canonicalize().unwrap();
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let mut calls: Vec<&str> = calls.lines().collect();
+    calls.sort();
+    assert_eq!(
+        calls,
+        [
+            format!(
+                "{}/api: docker compose -f compose.yml down",
+                stacks.display()
+            ),
+            format!(
+                "{}/web: docker compose -f compose.yml down",
+                stacks.display()
+            ),
+        ]
+    );
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains(&format!(
+        "| stop | compose:{}/api/compose.yml | SUCCESS",
+        stacks.display()
+    )));
+    assert!(history.contains(&format!(
+        "| stop | compose:{}/web/compose.yml | SUCCESS",
+        stacks.display()
+    )));
+
+    let output = run_stubbed(
+        &test_dir,
+        &["-C", "stacks/api", "-C", "stacks/web", "nginx"],
+    );
+    assert!(!output.status.success());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_stack_uses_config() {
+    let test_dir = create_temp_dir("compose_stack_config");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    write_user_config(
+        &test_dir,
+        "[defaults]\nhealth_check = \"echo checked >> \\\"$HOME/calls.log\\\"\"\n\n\
+         [services.shop]\nprotected = true\n",
+    );
+    for name in ["app", "shop"] {
+        let dir = test_dir.join("srv").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("compose.yml"), "services: {}\n").unwrap();
+    }
+
+    // [services.<project>] applies to the stack in a directory of that name
+    let output = run_stubbed(&test_dir, &["-C", "srv/shop"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("protected"));
+    let output = run_stubbed(&test_dir, &["-C", "srv/app", "-C", "srv/shop"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Total: 2, succeeded: 1, failed: 1"));
+
+    // ... and to that stack as a registered stack: target or a project step
+    assert!(
+        run_stubbed(&test_dir, &["stack", "add", "shop", "srv/shop"])
+            .status
+            .success()
+    );
+    let output = run_stubbed(&test_dir, &["stack:shop"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("protected"));
+    let project_dir = test_dir.join("srv").join("site");
+    fs::create_dir_all(&project_dir).unwrap();
+    fs::write(
+        project_dir.join(".tickle.toml"),
+        "[[step]]\ncompose = \"../shop/compose.yml\"\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["-C", "srv/site"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("protected"));
+
+    // [defaults] applies to every stack
+    assert!(run_stubbed(&test_dir, &["-C", "srv/app"]).status.success());
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert_eq!(calls.matches("checked").count(), 2);
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_change_directory_takes_globbed_directories() {
+    let test_dir = create_temp_dir("change_directory_glob");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  *" ps "*|"compose version") exit 0 ;;
+esac
+echo "$PWD: docker $*" >> "$HOME/calls.log"
+exit 0
+"#,
+    );
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    for name in ["a", "b"] {
+        let dir = test_dir.join("stacks").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("compose.yml"), "services: {}\n").unwrap();
+    }
+
+    // What the shell makes of `-C stacks/*`
+    let output = run_stubbed(&test_dir, &["-C", "stacks/a", "stacks/b"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Total: 2, succeeded: 2, failed: 0"));
+    let stacks = test_dir.join("stacks").canonicalize().unwrap();
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let mut calls: Vec<&str> = calls.lines().collect();
+    calls.sort();
+    assert_eq!(
+        calls,
+        [
+            format!(
+                "{}/a: docker compose -f compose.yml up -d --force-recreate",
+                stacks.display()
+            ),
+            format!(
+                "{}/b: docker compose -f compose.yml up -d --force-recreate",
+                stacks.display()
+            ),
+        ]
+    );
+
+    // A path that isn't a directory is never taken for a container
+    let output = run_stubbed(&test_dir, &["-C", "stacks/a", "stacks/missing"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("looks like a path"));
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert!(!calls.contains("restart"));
+
+    cleanup_dir(&test_dir);
+}
+
+/// A compose file exercising interpolation, anchors and the long syntaxes
+const INSPECT_COMPOSE_FILE: &str = r#"
+x-common: &common
+  restart: unless-stopped
+
+services:
+  web:
+    <<: *common
+    image: "nginx:${NGINX_TAG:-latest}"
+    ports:
+    - "8080:80"
+    depends_on:
+      api:
+        condition: service_healthy
+  api:
+    build:
+      context: ./api
+    healthcheck:
+      test: ["CMD", "curl", "-f", "http://localhost:3000/health"]  # probe
+"#;
+
+#[test]
+fn test_tickle_inspect_compose_file() {
+    let test_dir = create_temp_dir("compose_inspect");
+    fs::write(test_dir.join("compose.yml"), INSPECT_COMPOSE_FILE).unwrap();
+    fs::write(test_dir.join(".env"), "NGINX_TAG=1.25\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["inspect"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let web = stdout.lines().find(|l| l.starts_with("web")).unwrap();
+    assert!(web.contains("nginx:1.25"));
+    assert!(web.contains("8080:80"));
+    assert!(web.contains("api"));
+    let api = stdout.lines().find(|l| l.starts_with("api")).unwrap();
+    assert!(api.contains("(build ./api)"));
+    assert!(api.contains("curl -f http://localhost:3000/health"));
+
+    let output = run_stubbed(&test_dir, &["inspect", "db"]);
+    assert!(!output.status.success());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_invalid_compose_file_stops_before_down() {
+    let test_dir = create_temp_dir("compose_invalid");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n    depends_on: [db]\n",
+    )
+    .unwrap();
+
+    let output = run_stubbed(&test_dir, &["--compose-strategy", "down-up"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("service 'web' depends on undefined service 'db'"));
+
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx:${TAG:?TAG must be set}\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["--compose-strategy", "down-up"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("TAG must be set"));
+
+    assert!(!test_dir.join("calls.log").exists());
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("required variable TAG is missing a value"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_features_checked_by_compose() {
+    let test_dir = create_temp_dir("compose_unsupported");
+    write_stub(&test_dir, "docker", DOCKER_STUB);
+    let project = test_dir.join("app");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("db.yaml"),
+        "services:\n  db:\n    image: postgres\n",
+    )
+    .unwrap();
+
+    let files = [
+        (
+            "include:\n  - db.yaml\nservices:\n  web:\n    image: nginx\n    depends_on: [db]\n",
+            "`include:`",
+        ),
+        (
+            "services:\n  web:\n    image: nginx\n    ports: !reset []\n",
+            "the tag `!reset` on line 4",
+        ),
+        (
+            "services:\n  web:\n    image: nginx\n    command: \"sh -c 'echo a;\n      echo b'\"\n",
+            "a quoted string continuing past line 4",
+        ),
+    ];
+    for (contents, reason) in files {
+        fs::write(project.join("compose.yml"), contents).unwrap();
+        let output = run_stubbed(&test_dir, &["--dry-run", "-C", "app"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(stdout.contains(reason), "{}", stdout);
+        assert!(stdout.contains("checking with `docker compose config -q`"));
+    }
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert_eq!(calls.matches("config -q").count(), 3);
+
+    cleanup_dir(&test_dir);
+}
+
+/// A docker stub where `web` runs as container `old1` until it is scaled
+/// up, which adds `new1`; `$HOME/new-health` sets the new container's health
+const ROLLING_STUB: &str = r#"case "$*" in
+  "compose version") exit 0 ;;
+  *"config --services"*) echo web; exit 0 ;;
+  *" ps -q web")
+    echo old1
+    if [ -e "$HOME/scaled" ]; then echo new1; fi
+    exit 0
+    ;;
+  *" ps "*) exit 0 ;;
+  "container inspect"*)
+    echo "/app-web-2 running $(cat "$HOME/new-health")"
+    exit 0
+    ;;
+  *"--scale"*) touch "$HOME/scaled" ;;
+esac
+echo "docker $*" >> "$HOME/calls.log"
+exit 0
+"#;
+
+#[test]
+fn test_tickle_compose_rolling_restart() {
+    let test_dir = create_temp_dir("compose_rolling");
+    write_stub(&test_dir, "docker", ROLLING_STUB);
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n",
+    )
+    .unwrap();
+    fs::write(test_dir.join("new-health"), "healthy\n").unwrap();
+
+    let output = run_stubbed(&test_dir, &["--compose-strategy", "rolling", "web"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Replaced web without downtime"));
+
+    // A new container that turns unhealthy is removed and the old one kept
+    fs::remove_file(test_dir.join("scaled")).unwrap();
+    fs::write(test_dir.join("new-health"), "unhealthy\n").unwrap();
+    let output = run_stubbed(&test_dir, &["--compose-strategy", "rolling", "web"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("app-web-2 is running, unhealthy"));
+
+    let output = run_stubbed(&test_dir, &["--compose-strategy", "rolling"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("needs service names"));
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    let scale = "docker compose -f compose.yml up -d --no-deps --no-recreate --scale web=2 web";
+    assert_eq!(
+        calls,
+        [
+            scale,
+            "docker stop old1",
+            "docker rm old1",
+            scale,
+            "docker rm -f new1"
+        ]
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_services_follow_depends_on() {
+    let test_dir = create_temp_dir("compose_depends_on");
+    write_stub(
+        &test_dir,
+        "docker",
+        &format!(
+            "case \"$*\" in *\"config --services\"*) printf 'web\\napi\\ndb\\n'; exit 0 ;; esac\n{}",
+            DOCKER_STUB
+        ),
+    );
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n    depends_on: [api]\n  \
+         api:\n    image: api\n    depends_on:\n      db:\n        condition: service_healthy\n  \
+         db:\n    image: postgres\n",
+    )
+    .unwrap();
+
+    assert!(
+        run_stubbed(&test_dir, &["web", "db", "api"])
+            .status
+            .success()
+    );
+    assert!(
+        run_stubbed(&test_dir, &["stop", "db", "web", "api"])
+            .status
+            .success()
+    );
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "docker compose -f compose.yml restart db",
+            "docker compose -f compose.yml restart api",
+            "docker compose -f compose.yml restart web",
+            "docker compose -f compose.yml stop web",
+            "docker compose -f compose.yml stop api",
+            "docker compose -f compose.yml stop db",
+        ]
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_compose_context() {
+    let test_dir = create_temp_dir("compose_context");
+    write_stub(
+        &test_dir,
+        "docker",
+        "case \"$*\" in *\" ps \"*|\"compose version\") exit 0 ;; esac\n\
+         echo \"[$DOCKER_CONTEXT|$DOCKER_HOST] docker $*\" >> \"$HOME/calls.log\"\n",
+    );
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n",
+    )
+    .unwrap();
+
+    // --context wins over DOCKER_HOST, log following included
+    let output = Command::new(get_tickle_binary())
+        .args(["--context", "prod", "-f"])
+        .current_dir(&test_dir)
+        .env("HOME", &test_dir)
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("COMPOSE_FILE")
+        .env_remove("COMPOSE_PROFILES")
+        .env_remove("DOCKER_CONTEXT")
+        .env("DOCKER_HOST", "tcp://staging:2376")
+        .env("PATH", stub_path(&test_dir))
+        .output()
+        .expect("Failed to execute tickle");
+    assert!(output.status.success());
+
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let calls: Vec<&str> = calls.lines().collect();
+    assert_eq!(
+        calls,
+        [
+            "[prod|] docker compose -f compose.yml up -d --force-recreate",
+            "[prod|] docker compose -f compose.yml logs -f",
+        ]
+    );
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    assert!(history.contains("| SUCCESS | context: prod"));
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_status() {
+    let test_dir = create_temp_dir("status");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  *"config --services"*) printf 'web\ndb\n' ;;
+  *" ps "*) printf 'app-web-1\tweb\trunning\thealthy\tUp 2 hours (healthy)\t0.0.0.0:8080->80/tcp\n' ;;
+  "container inspect"*) echo "/app-web-1 3" ;;
+  *" restart"*|*" up "*|*" stop"*|*" down"*) echo "docker $*" >> "$HOME/calls.log" ;;
+esac
+exit 0
+"#,
+    );
+    write_stub(
+        &test_dir,
+        "systemctl",
+        r#"case "$1" in
+  show)
+    case "$2" in
+      missing) echo "LoadState=not-found" ;;
+      *) printf 'LoadState=loaded\nActiveState=active\nSubState=running\nMainPID=1234\nMemoryCurrent=13107200\nActiveEnterTimestamp=Mon 2024-02-05 14:40:02 UTC\nActiveEnterTimestampMonotonic=0\n' ;;
+    esac
+    ;;
+  restart|start|stop) echo "systemctl $*" >> "$HOME/calls.log" ;;
+esac
+exit 0
+"#,
+    );
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n  db:\n    image: postgres\n",
+    )
+    .unwrap();
+
+    let output = run_stubbed(&test_dir, &["status"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let web = stdout.lines().find(|l| l.starts_with("web ")).unwrap();
+    let web: Vec<&str> = web.split_whitespace().collect();
+    assert_eq!(
+        web,
+        [
+            "web",
+            "app-web-1",
+            "running",
+            "healthy",
+            "2",
+            "hours",
+            "3",
+            "0.0.0.0:8080->80/tcp"
+        ]
+    );
+    let db = stdout.lines().find(|l| l.starts_with("db ")).unwrap();
+    assert!(db.contains("no container"));
+
+    let output = run_stubbed(&test_dir, &["status", "--systemd", "nginx", "missing"]);
+    assert!(!output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("State:   active (running)"));
+    assert!(stdout.contains("PID:     1234"));
+    assert!(stdout.contains("Memory:  12.5M"));
+    assert!(stdout.contains("Started: Mon 2024-02-05 14:40:02 UTC"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing: no such systemd unit"));
+
+    // Status never changes anything, or records history
+    assert!(!test_dir.join("calls.log").exists());
+    assert!(!test_dir.join(".tickle/history.log").exists());
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_logs_command() {
+    let test_dir = create_temp_dir("logs_command");
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  *"config --services"*) printf 'web\ndb\n' ;;
+  *" logs "*)
+    echo "docker $*" >> "$HOME/calls.log"
+    printf 'web-1  | GET / 200\nweb-1  | ERROR: upstream timed out\nweb-1  | errors=0\n'
+    printf 'web-1  | Warning: slow request (1520ms)\n' >&2
+    ;;
+esac
+exit 0
+"#,
+    );
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n  db:\n    image: postgres\n",
+    )
+    .unwrap();
+
+    let output = run_stubbed(
+        &test_dir,
+        &[
+            "logs",
+            "web",
+            "--no-follow",
+            "--since",
+            "10m",
+            "--lines",
+            "50",
+            "--grep",
+            r"(?i)\berror\b|\(\d{4,}ms\)$",
+        ],
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.trim(), "web-1  | ERROR: upstream timed out");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.trim(), "web-1  | Warning: slow request (1520ms)");
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert_eq!(
+        calls.trim(),
+        "docker compose -f compose.yml logs --since 600s --tail 50 web"
+    );
+
+    let output = run_stubbed(
+        &test_dir,
+        &[
+            "logs",
+            "--systemd",
+            "nginx",
+            "--since",
+            "2h",
+            "--priority",
+            "err",
+            "--dry-run",
+        ],
+    );
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stdout)
+            .contains("Would run: journalctl -f -u nginx --since -7200s -p err")
+    );
+
+    let output = run_stubbed(&test_dir, &["logs", "web", "--priority", "err"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("--priority only applies to systemd units")
+    );
+
+    let output = run_stubbed(&test_dir, &["logs", "--grep", "(unclosed"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Invalid --grep pattern: unclosed group")
+    );
+
+    let output = run_stubbed(&test_dir, &["web", "--since", "10m"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("--since option only valid with logs command")
+    );
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_show_logs() {
+    let test_dir = create_temp_dir("show_logs");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    write_stub(
+        &test_dir,
+        "journalctl",
+        r#"case "$*" in
+  *--show-cursor*) echo "-- cursor: s=abc;i=42"; exit 0 ;;
+esac
+echo "journalctl $*" >> "$HOME/calls.log"
+echo "nginx[99]: loading config"
+echo "nginx[99]: Started nginx, ready for connections"
+echo "nginx[99]: a later line"
+exec sleep 10
+"#,
+    );
+    write_user_config(
+        &test_dir,
+        "[services.nginx]\nready_log = \"ready for connections$\"\n",
+    );
+
+    let started = std::time::Instant::now();
+    let output = run_stubbed(&test_dir, &["--systemd", "--show-logs", "nginx"]);
+    assert!(output.status.success());
+    assert!(started.elapsed().as_secs() < 5);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("nginx[99]: loading config"));
+    assert!(stdout.contains("✅ nginx is ready"));
+    assert!(!stdout.contains("a later line"));
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    assert_eq!(
+        calls.trim(),
+        "journalctl -f -u nginx --after-cursor s=abc;i=42"
+    );
+
+    // A failed compose restart shows what was logged since, then fails
+    fs::remove_file(test_dir.join("calls.log")).unwrap();
+    write_stub(
+        &test_dir,
+        "docker",
+        r#"case "$*" in
+  *" logs "*) echo "docker $*" >> "$HOME/calls.log"; echo "web-1  | panic: bad config" ;;
+  *" up "*) echo "web-1 exited" >&2; exit 1 ;;
+esac
+exit 0
+"#,
+    );
+    fs::write(
+        test_dir.join("compose.yml"),
+        "services:\n  web:\n    image: nginx\n",
+    )
+    .unwrap();
+    let output = run_stubbed(&test_dir, &["--show-logs"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("web-1  | panic: bad config"));
+    let calls = fs::read_to_string(test_dir.join("calls.log")).unwrap();
+    let since = calls
+        .trim()
+        .strip_prefix("docker compose -f compose.yml logs --since ")
+        .unwrap();
+    let (secs, nanos) = since.split_once('.').unwrap();
+    assert!(secs.parse::<u64>().unwrap() > 1_700_000_000);
+    assert_eq!(nanos.len(), 9);
+
+    cleanup_dir(&test_dir);
+}
+
+#[test]
+fn test_tickle_until_log() {
+    let test_dir = create_temp_dir("until_log");
+    write_stub(&test_dir, "systemctl", SYSTEMCTL_STUB);
+    // Prints `$HOME/journal.txt` as the unit's new lines, then keeps following
+    write_stub(
+        &test_dir,
+        "journalctl",
+        r#"case "$*" in
+  *--show-cursor*) echo "-- cursor: s=abc"; exit 0 ;;
+esac
+cat "$HOME/journal.txt"
+exec sleep 10
+"#,
+    );
+    let until_log = |journal: &str, args: &[&str]| {
+        fs::write(test_dir.join("journal.txt"), journal).unwrap();
+        let mut all = vec![
+            "--systemd",
+            "--until-log",
+            "listening on port \\d+",
+            "nginx",
+        ];
+        all.extend(args);
+        run_stubbed(&test_dir, &all)
+    };
+
+    let output = until_log("starting\nlistening on port 8080\n", &[]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("✅ nginx is ready"));
+
+    let started = std::time::Instant::now();
+    let output = until_log("starting\n", &["--ready-timeout", "1", "--timeout", "30"]);
+    assert!(!output.status.success());
+    assert!(started.elapsed().as_secs() < 5);
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("no log line matched 'listening on port \\d+' within 1s")
+    );
+
+    let output = until_log(
+        "FATAL: address already in use\nlistening on port 8080\n",
+        &["--fail-log", "(?i)fatal"],
+    );
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("failure pattern matched: FATAL: address already in use")
+    );
+
+    // --fail-log on its own shows the new lines to check them
+    let output = run_stubbed(
+        &test_dir,
+        &["--systemd", "--fail-log", "(?i)fatal", "nginx"],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("FATAL: address already in use"));
+
+    let history = fs::read_to_string(test_dir.join(".tickle/history.log")).unwrap();
+    let waits: Vec<&str> = history
+        .lines()
+        .filter(|line| line.contains("| until-log | nginx |"))
+        .collect();
+    assert_eq!(waits.len(), 4);
+    assert!(waits[0].ends_with("| SUCCESS | matched: listening on port 8080"));
+    assert!(waits[1].contains("| FAILED | no log line matched"));
+    assert!(waits[2].contains("| FAILED | failure pattern matched"));
+
+    cleanup_dir(&test_dir);
+}